@@ -0,0 +1,293 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    bracketed, parenthesized, parse_macro_input, Data, DeriveInput, Error, Expr, Field, Fields,
+    Ident, LitFloat, LitInt, LitStr, Result, Token,
+};
+
+/// Derives `ElementProperties`, `Default` and a `new_<name>()` constructor returning an `Element`.
+///
+/// The struct needs a `color: Color` and a `visited: bool` field,
+/// all other fields are initialized with `Default::default()`.
+/// The color and fields marked with `#[element(state)]` are part of the saved cell state.
+///
+/// ```ignore
+/// #[derive(Clone, ElementProperties)]
+/// #[element(name = "Sand", color = (239, 221, 111), swap_priority = 212, behaviour = PowderBehaviour)]
+/// pub struct SandProperties {
+///     color: Color,
+///     visited: bool,
+///     #[element(state)]
+///     lifetime: u32,
+/// }
+/// ```
+///
+/// # Attributes
+///
+/// * `name` - Name of the element
+/// * `color` - Default color as `(red, green, blue)` or `(red, green, blue, alpha)`
+/// * `swap_priority` - See `ElementProperties::swap_priority`
+/// * `behaviour` - Expression evaluating to the behaviour of the element
+/// * `tags` - Optional list of tags like `[Powder, Soluble]`
+/// * `emission` - Optional glow between 0 and 1, see `ElementProperties::emission`
+/// * `constructor` - Optional name of the constructor, defaults to `new_` followed by the name in snake case
+#[proc_macro_derive(ElementProperties, attributes(element))]
+pub fn derive_element_properties(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct ElementAttributes {
+    name: LitStr,
+    color: Vec<LitInt>,
+    swap_priority: LitInt,
+    behaviour: Expr,
+    tags: Vec<Ident>,
+    emission: Option<LitFloat>,
+    constructor: Ident,
+}
+
+fn parse_attributes(input: &DeriveInput) -> Result<ElementAttributes> {
+    let mut name = None;
+    let mut color = None;
+    let mut swap_priority = None;
+    let mut behaviour = None;
+    let mut tags = Vec::new();
+    let mut emission = None;
+    let mut constructor = None;
+
+    for attr in input.attrs.iter().filter(|x| x.path().is_ident("element")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("color") {
+                let value = meta.value()?;
+                let content;
+                parenthesized!(content in value);
+                let channels = Punctuated::<LitInt, Token![,]>::parse_terminated(&content)?;
+                if !(3..=4).contains(&channels.len()) {
+                    return Err(
+                        meta.error("expected (red, green, blue) or (red, green, blue, alpha)")
+                    );
+                }
+                color = Some(channels.into_iter().collect());
+            } else if meta.path.is_ident("swap_priority") {
+                swap_priority = Some(meta.value()?.parse::<LitInt>()?);
+            } else if meta.path.is_ident("behaviour") {
+                behaviour = Some(meta.value()?.parse::<Expr>()?);
+            } else if meta.path.is_ident("tags") {
+                let value = meta.value()?;
+                let content;
+                bracketed!(content in value);
+                tags = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect();
+            } else if meta.path.is_ident("emission") {
+                let value = meta.value()?.parse::<LitFloat>()?;
+                if !(0.0..=1.0).contains(&value.base10_parse::<f32>()?) {
+                    return Err(Error::new_spanned(
+                        value,
+                        "emission has to be between 0 and 1",
+                    ));
+                }
+                emission = Some(value);
+            } else if meta.path.is_ident("constructor") {
+                constructor = Some(meta.value()?.parse::<Ident>()?);
+            } else {
+                return Err(meta.error("unknown element attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let missing = |attribute: &str| {
+        Error::new(
+            Span::call_site(),
+            format!("missing #[element({attribute} = ...)] attribute"),
+        )
+    };
+    let name = name.ok_or_else(|| missing("name"))?;
+    let constructor =
+        constructor.unwrap_or_else(|| format_ident!("new_{}", snake_case(&name.value())));
+    Ok(ElementAttributes {
+        color: color.ok_or_else(|| missing("color"))?,
+        swap_priority: swap_priority.ok_or_else(|| missing("swap_priority"))?,
+        behaviour: behaviour.ok_or_else(|| missing("behaviour"))?,
+        tags,
+        emission,
+        name,
+        constructor,
+    })
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let attributes = parse_attributes(&input)?;
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ElementProperties can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ElementProperties requires named fields",
+        ));
+    };
+    for required in ["color", "visited"] {
+        if !fields
+            .named
+            .iter()
+            .any(|x| x.ident.as_ref().is_some_and(|x| x == required))
+        {
+            return Err(Error::new_spanned(
+                &input.ident,
+                format!("ElementProperties requires a `{required}` field"),
+            ));
+        }
+    }
+
+    let ident = &input.ident;
+    let ElementAttributes {
+        name,
+        color,
+        swap_priority,
+        behaviour,
+        tags,
+        emission,
+        constructor,
+    } = attributes;
+    let emission = emission.map(|emission| {
+        quote! {
+            fn emission(&self) -> f32 {
+                #emission
+            }
+        }
+    });
+    let new_color = if color.len() == 4 {
+        quote!(::falling_sand::color::Color::new_with_alpha(#(#color),*))
+    } else {
+        quote!(::falling_sand::color::Color::new(#(#color),*))
+    };
+    let mut state_fields = Vec::new();
+    for field in &fields.named {
+        if is_state_field(field)? {
+            state_fields.extend(&field.ident);
+        }
+    }
+    let state_keys = state_fields.iter().map(|x| x.to_string());
+    let state_keys_read = state_keys.clone();
+    let other_fields = fields
+        .named
+        .iter()
+        .filter_map(|x| x.ident.as_ref())
+        .filter(|x| *x != "color" && *x != "visited");
+    let constructor_doc = format!("Creates a new {} element", name.value());
+
+    Ok(quote! {
+        impl ::falling_sand::elements::element::ElementProperties for #ident {
+            fn name(&self) -> &str {
+                #name
+            }
+
+            fn color(&self) -> ::falling_sand::color::Color {
+                self.color
+            }
+
+            fn visited(&self) -> bool {
+                self.visited
+            }
+
+            fn set_visited(&mut self, value: bool) {
+                self.visited = value;
+            }
+
+            fn swap_priority(&self) -> u8 {
+                #swap_priority
+            }
+
+            fn tags(&self) -> ::falling_sand::elements::tags::Tags {
+                ::falling_sand::elements::tags::Tags::new(&[
+                    #(::falling_sand::elements::tags::Tag::#tags),*
+                ])
+            }
+
+            #emission
+
+            fn state(&self) -> ::falling_sand::elements::state::CellState {
+                let mut state = ::falling_sand::elements::state::CellState::new();
+                state.insert("color", &self.color);
+                #(state.insert(#state_keys, &self.#state_fields);)*
+                state
+            }
+
+            fn set_state(
+                &mut self,
+                state: &::falling_sand::elements::state::CellState,
+            ) -> ::falling_sand::anyhow::Result<()> {
+                state.read_into("color", &mut self.color)?;
+                #(state.read_into(#state_keys_read, &mut self.#state_fields)?;)*
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        impl ::core::default::Default for #ident {
+            fn default() -> Self {
+                #ident {
+                    color: #new_color,
+                    visited: false,
+                    #(#other_fields: ::core::default::Default::default(),)*
+                }
+            }
+        }
+
+        #[doc = #constructor_doc]
+        pub fn #constructor() -> ::falling_sand::elements::element::Element {
+            ::falling_sand::elements::element::Element {
+                properties: ::std::boxed::Box::<#ident>::default(),
+                behaviour: ::std::sync::Arc::new(#behaviour),
+            }
+        }
+    })
+}
+
+/// Checks for `#[element(state)]` on a field. Other field attributes are rejected
+fn is_state_field(field: &Field) -> Result<bool> {
+    let mut state = false;
+    for attr in field.attrs.iter().filter(|x| x.path().is_ident("element")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("state") {
+                state = true;
+                Ok(())
+            } else {
+                Err(Error::new_spanned(&meta.path, "unknown element attribute"))
+            }
+        })?;
+    }
+    Ok(state)
+}
+
+/// Converts names like "Sand", "Molten Glass" or "MoltenGlass" to "molten_glass"
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous_lowercase = false;
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if c.is_uppercase() && previous_lowercase {
+                snake.push('_');
+            }
+            previous_lowercase = c.is_lowercase() || c.is_numeric();
+            snake.extend(c.to_lowercase());
+        } else {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            previous_lowercase = false;
+        }
+    }
+    snake.trim_end_matches('_').to_string()
+}
@@ -0,0 +1,162 @@
+//! Text grids where every character is a cell, meant for tests and fixtures.
+//!
+//! ```text
+//! ..S.
+//! .WW.
+//! ####
+//! ```
+
+use crate::elements::registry::ElementRegistry;
+use crate::idx;
+use crate::simulation::{empty_matrix, ElementMatrix, Simulation};
+use anyhow::{bail, Context, Result};
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+
+/// Printed for elements which aren't part of the legend
+pub const UNKNOWN: char = '?';
+
+/// Maps characters to element names and back
+#[derive(Clone, Debug, PartialEq)]
+pub struct Legend {
+    pub empty: char,
+    entries: Vec<(char, String)>,
+}
+
+impl Legend {
+    /// Creates a legend without elements where `empty` stands for empty cells.
+    /// Whitespace doesn't work as symbol, because it is trimmed when parsing
+    pub fn new(empty: char) -> Self {
+        Legend {
+            empty,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Maps `symbol` to the element named `name`, replacing a previous mapping of either
+    pub fn with(mut self, symbol: char, name: &str) -> Self {
+        self.entries.retain(|(x, y)| *x != symbol && y != name);
+        self.entries.push((symbol, name.to_string()));
+        self
+    }
+
+    pub fn name_of(&self, symbol: char) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(x, _)| *x == symbol)
+            .map(|(_, name)| name.as_str())
+    }
+
+    pub fn symbol_of(&self, name: &str) -> Option<char> {
+        self.entries
+            .iter()
+            .find(|(_, x)| x == name)
+            .map(|(symbol, _)| *symbol)
+    }
+}
+
+impl Default for Legend {
+    /// `.` empty, `S` sand, `W` water and `#` stone
+    fn default() -> Self {
+        Legend::new('.')
+            .with('S', "Sand")
+            .with('W', "Water")
+            .with('#', "Stone")
+    }
+}
+
+/// Parses a text grid into a matrix. Leading and trailing whitespace of every line and empty lines
+/// are ignored, so grids can be indented in source code. All lines need the same length
+///
+/// # Arguments
+///
+/// * `text` - The grid, one line per row
+/// * `legend` - Maps characters to element names
+/// * `registry` - Registry the elements are created with
+/// * `rng` - Source of the color variation of the created elements
+pub fn parse_ascii(
+    text: &str,
+    legend: &Legend,
+    registry: &ElementRegistry,
+    rng: &mut SplitMix64,
+) -> Result<ElementMatrix> {
+    let rows = text
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| x.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let width = rows.first().map_or(0, Vec::len);
+
+    let mut matrix = empty_matrix(width, rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() != width {
+            bail!(
+                "Line {} is {} characters long, expected {width}",
+                y + 1,
+                row.len()
+            );
+        }
+        for (x, &symbol) in row.iter().enumerate() {
+            if symbol == legend.empty {
+                continue;
+            }
+            let name = legend.name_of(symbol).with_context(|| {
+                format!(
+                    "Unknown character {symbol:?} in line {}, column {}",
+                    y + 1,
+                    x + 1
+                )
+            })?;
+            let element = registry
+                .create_by_name(name, rng)
+                .with_context(|| format!("Element {name:?} of {symbol:?} isn't registered"))?;
+            matrix[idx!(Vector2::new(x, y))] = Some(element);
+        }
+    }
+    Ok(matrix)
+}
+
+/// Prints a matrix as text grid with a line break after every row.
+/// Elements missing from the legend are printed as [`UNKNOWN`]
+pub fn to_ascii(matrix: &ElementMatrix, legend: &Legend) -> String {
+    let mut text = String::with_capacity((matrix.ncols() + 1) * matrix.nrows());
+    for y in 0..matrix.nrows() {
+        for x in 0..matrix.ncols() {
+            text.push(match &matrix[idx!(Vector2::new(x, y))] {
+                None => legend.empty,
+                Some(element) => legend
+                    .symbol_of(element.properties.name())
+                    .unwrap_or(UNKNOWN),
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+impl Simulation {
+    /// Creates a simulation of the builtin elements from a grid in the default legend,
+    /// see [`parse_ascii`]. The random number generator is seeded with 0, so runs are reproducible
+    pub fn from_ascii(text: &str) -> Result<Simulation> {
+        let mut simulation = Simulation::new_with_rand(0, 0, SplitMix64::seed_from_u64(0));
+        simulation.load_ascii(text, &Legend::default())?;
+        Ok(simulation)
+    }
+
+    /// Replaces the matrix with a parsed grid, creating elements through the registry of the simulation
+    pub fn load_ascii(&mut self, text: &str, legend: &Legend) -> Result<()> {
+        self.matrix = parse_ascii(text, legend, &self.registry, &mut self.rng)?;
+        Ok(())
+    }
+
+    /// Prints the matrix in the default legend, see [`to_ascii`]
+    pub fn to_ascii(&self) -> String {
+        to_ascii(&self.matrix, &Legend::default())
+    }
+
+    pub fn to_ascii_with(&self, legend: &Legend) -> String {
+        to_ascii(&self.matrix, legend)
+    }
+}
@@ -0,0 +1,139 @@
+//! Records a simulation run as animated PNG or GIF without a window or GPU.
+//!
+//! ```text
+//! record <output.png|output.gif> [--ticks N] [--skip N] [--scale N] [--size N]
+//!        [--scene scene.png] [--elements elements.ron] [--seed N]
+//! ```
+
+use anyhow::{bail, Context, Result};
+use falling_sand::elements::definition::load_definitions_file;
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use falling_sand::image::animation::{record_animation_file, AnimationFormat, AnimationOptions};
+use falling_sand::image::import::{import_png_file, Palette};
+use falling_sand::image::raster::RasterOptions;
+use falling_sand::simulation::Simulation;
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+struct Arguments {
+    output: PathBuf,
+    ticks: u64,
+    frame_skip: u32,
+    scale: u32,
+    size: usize,
+    scene: Option<PathBuf>,
+    elements: Option<PathBuf>,
+    seed: u64,
+}
+
+fn parse_arguments() -> Result<Arguments> {
+    let mut args = std::env::args().skip(1);
+    let mut arguments = Arguments {
+        output: PathBuf::new(),
+        ticks: 300,
+        frame_skip: 2,
+        scale: 4,
+        size: 100,
+        scene: None,
+        elements: None,
+        seed: 0,
+    };
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value of {arg}"))
+        };
+        match arg.as_str() {
+            "--ticks" => arguments.ticks = value()?.parse().context("Invalid --ticks")?,
+            "--skip" => arguments.frame_skip = value()?.parse().context("Invalid --skip")?,
+            "--scale" => arguments.scale = value()?.parse().context("Invalid --scale")?,
+            "--size" => arguments.size = value()?.parse().context("Invalid --size")?,
+            "--seed" => arguments.seed = value()?.parse().context("Invalid --seed")?,
+            "--scene" => arguments.scene = Some(value()?.into()),
+            "--elements" => arguments.elements = Some(value()?.into()),
+            flag if flag.starts_with("--") => bail!("Unknown option {flag}"),
+            path if output.is_none() => output = Some(PathBuf::from(path)),
+            other => bail!("Unexpected argument {other}"),
+        }
+    }
+    arguments.output = output.context("Missing output path")?;
+    Ok(arguments)
+}
+
+/// A heap of sand next to a pool of water on a stone floor
+fn demo_scene(simulation: &mut Simulation) {
+    let size = simulation.matrix.ncols();
+    let registry = Arc::clone(&simulation.registry);
+    for x in 0..size {
+        for y in 0..size {
+            let pos = Vector2::new(x, y);
+            let id = if y == size - 1 {
+                Some(ElementId::STONE)
+            } else if y < size / 3 && x < size / 2 && x >= size / 8 {
+                Some(ElementId::SAND)
+            } else if y < size / 2 && x > size / 2 {
+                Some(ElementId::WATER)
+            } else {
+                None
+            };
+            simulation.matrix[falling_sand::idx!(pos)] =
+                id.and_then(|id| registry.create(id, &mut simulation.rng));
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let arguments = parse_arguments()?;
+
+    let mut registry = ElementRegistry::builtin();
+    if let Some(path) = &arguments.elements {
+        load_definitions_file(path, &mut registry)?;
+    }
+    let mut simulation = Simulation::new_with_rand(
+        arguments.size,
+        arguments.size,
+        SplitMix64::seed_from_u64(arguments.seed),
+    );
+    simulation.registry = Arc::new(registry);
+
+    match &arguments.scene {
+        Some(path) => {
+            let scene = import_png_file(
+                path,
+                &Palette::from_registry(&simulation.registry),
+                &simulation.registry,
+                &mut simulation.rng,
+            )?;
+            for unmapped in &scene.unmapped {
+                eprintln!(
+                    "{} pixels with color {:?} didn't match any element, first at {}, {}",
+                    unmapped.count, unmapped.color, unmapped.first.x, unmapped.first.y
+                );
+            }
+            simulation.matrix = scene.matrix;
+        }
+        None => demo_scene(&mut simulation),
+    }
+
+    let options = AnimationOptions {
+        format: AnimationFormat::from_path(&arguments.output),
+        frame_skip: arguments.frame_skip,
+        raster: RasterOptions {
+            scale: arguments.scale,
+            ..RasterOptions::default()
+        },
+        ..AnimationOptions::default()
+    };
+    record_animation_file(
+        &mut simulation,
+        arguments.ticks,
+        &options,
+        &arguments.output,
+    )?;
+    println!("Recorded {}", arguments.output.display());
+    Ok(())
+}
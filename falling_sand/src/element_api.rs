@@ -1,14 +1,46 @@
 use crate::elements::element::Element;
+use crate::elements::tags::{Tag, Tags};
 use crate::idx;
-use crate::simulation::ElementMatrix;
+use crate::line::Line;
+use crate::simulation::{ElementMatrix, TickContext};
+use crate::NEIGHBOURS;
+use anyhow::{anyhow, Context, Result};
 use nalgebra::Vector2;
 use rand_core::RngCore;
 use rand_xoshiro::SplitMix64;
+use std::ops::Range;
+
+/// Describes what stopped a movement or a ray
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Obstacle {
+    /// An element at the given relative position
+    Element(Vector2<isize>),
+    /// The border of the matrix. Holds the first relative position that is out of bounds
+    OutOfBounds(Vector2<isize>),
+}
+
+/// The outcome of [`ElementApi::move_towards`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Movement {
+    /// Offset from the starting position to the position the element ended up at
+    pub offset: Vector2<isize>,
+    /// What stopped the movement early, relative to the new position.
+    /// `None` if every step was taken
+    pub obstacle: Option<Obstacle>,
+}
+
+impl Movement {
+    /// Returns true if the element moved at least one cell
+    pub fn moved(&self) -> bool {
+        self.offset != Vector2::zeros()
+    }
+}
 
 /// A wrapper around an ElementMatrix which provides helper methods for element implementations
 pub struct ElementApi<'a> {
     pub matrix: &'a mut ElementMatrix,
     pub rng: &'a mut SplitMix64,
+    pub context: TickContext<'a>,
     pub position: Vector2<usize>,
 }
 
@@ -17,34 +49,66 @@ impl<'a> ElementApi<'a> {
     ///
     /// * `matrix` - The ElementMatrix
     /// * `rng` - The random number generator
+    /// * `context` - The tick count, world parameters and registry of the simulation
     /// * `position` - The absolute position to the current element. Needs to be a valid position containing an element
     pub fn new(
         matrix: &'a mut ElementMatrix,
         rng: &'a mut SplitMix64,
+        context: TickContext<'a>,
         position: Vector2<usize>,
     ) -> Self {
         Self {
             matrix,
             rng,
+            context,
             position,
         }
     }
 
-    /// Flips the visited flag of the current element
+    /// Same as [`ElementApi::new`] but returns an error if `position` is out of bounds
+    /// or doesn't contain an element
+    pub fn try_new(
+        matrix: &'a mut ElementMatrix,
+        rng: &'a mut SplitMix64,
+        context: TickContext<'a>,
+        position: Vector2<usize>,
+    ) -> Result<Self> {
+        let mut api = Self::new(matrix, rng, context, position);
+        api.try_element()
+            .context("ElementApi expects valid position containing element")?;
+        Ok(api)
+    }
+
+    /// Flips the visited flag of the current element.
+    /// Does nothing if the current element is missing
     pub fn flip_visited(&mut self) {
-        let element = self.element();
-        element
-            .properties
-            .set_visited(!element.properties.visited());
+        if let Ok(element) = self.try_element() {
+            element
+                .properties
+                .set_visited(!element.properties.visited());
+        }
     }
 
     /// Returns the current element
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current position is out of bounds or doesn't contain an element,
+    /// see [`ElementApi::try_element`] for a non panicking version
     pub fn element(&mut self) -> &mut Element {
+        self.try_element().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Returns the current element or an error if the current position
+    /// is out of bounds or doesn't contain an element.
+    /// This happens when a behaviour writes into the matrix directly
+    pub fn try_element(&mut self) -> Result<&mut Element> {
+        let position = self.position;
         self.matrix
-            .get_mut(idx!(self.position))
-            .expect("ElementApi expects valid position")
+            .get_mut(idx!(position))
+            .ok_or_else(|| anyhow!("Position {position:?} is out of bounds"))?
             .as_mut()
-            .expect("ElementApi expects position containing element")
+            .ok_or_else(|| anyhow!("Position {position:?} doesn't contain an element"))
     }
 
     /// Returns the element at `pos`
@@ -59,14 +123,40 @@ impl<'a> ElementApi<'a> {
             .and_then(|x| x.as_mut())
     }
 
+    /// Returns the tags of the element at `rel_pos`, which are empty if there is no element
+    ///
+    /// # Arguments
+    ///
+    /// * `rel_pos` - Relative position to the element
+    pub fn tags_at(&self, rel_pos: Vector2<isize>) -> Tags {
+        (self.position.cast() + rel_pos)
+            .try_cast::<usize>()
+            .and_then(|pos| self.matrix.get(idx!(pos)))
+            .and_then(|cell| cell.as_ref())
+            .map_or(Tags::empty(), |element| element.properties.tags())
+    }
+
+    /// Checks if the element at `rel_pos` has `tag`. Use `Vector2::zeros()` for the current element
+    ///
+    /// # Arguments
+    ///
+    /// * `rel_pos` - Relative position to the element
+    /// * `tag` - The tag to check for
+    pub fn has_tag(&self, rel_pos: Vector2<isize>, tag: Tag) -> bool {
+        self.tags_at(rel_pos).contains(tag)
+    }
+
     /// Swaps `self.position` with `other_pos` if possible.
-    /// Returns true if the swap was successful
+    /// Returns true if the swap was successful, false if the current element is missing
     ///
     /// # Arguments
     ///
     /// * `other_pos` - Relative position to the element to swap with
     pub fn swap(&mut self, other_pos: Vector2<isize>) -> bool {
-        let swap_priority = self.element().properties.swap_priority();
+        let Ok(element) = self.try_element() else {
+            return false;
+        };
+        let swap_priority = element.properties.swap_priority();
         if self
             .other_element(other_pos)
             .is_some_and(|other| other.properties.swap_priority() >= swap_priority)
@@ -85,9 +175,160 @@ impl<'a> ElementApi<'a> {
         }
     }
 
+    /// Moves the current element along a line towards `target_rel`, swapping one cell at a time.
+    /// Stops before the first cell it can't swap with, see [`ElementApi::swap`]
+    ///
+    /// # Arguments
+    ///
+    /// * `target_rel` - Relative position the element moves towards
+    /// * `max_steps` - Maximum amount of cells the element moves
+    pub fn move_towards(&mut self, target_rel: Vector2<isize>, max_steps: usize) -> Movement {
+        let mut offset = Vector2::zeros();
+        let mut previous = Vector2::zeros();
+        for point in Line::new(Vector2::zeros(), target_rel).take(max_steps) {
+            let step = point - previous;
+            previous = point;
+
+            let obstacle = if !self.in_bounds(step) {
+                Obstacle::OutOfBounds(step)
+            } else if !self.swap(step) {
+                Obstacle::Element(step)
+            } else {
+                offset += step;
+                continue;
+            };
+            return Movement {
+                offset,
+                obstacle: Some(obstacle),
+            };
+        }
+
+        Movement {
+            offset,
+            obstacle: None,
+        }
+    }
+
+    /// Follows a line towards `target_rel` and returns the first element or border it hits.
+    /// Returns `None` if the line is clear
+    ///
+    /// # Arguments
+    ///
+    /// * `target_rel` - Relative position the ray is cast towards
+    /// * `max_steps` - Maximum amount of cells the ray travels
+    pub fn raycast(&self, target_rel: Vector2<isize>, max_steps: usize) -> Option<Obstacle> {
+        Line::new(Vector2::zeros(), target_rel)
+            .take(max_steps)
+            .find_map(|point| {
+                if !self.in_bounds(point) {
+                    return Some(Obstacle::OutOfBounds(point));
+                }
+                let pos = (self.position.cast() + point).try_cast::<usize>()?;
+                self.matrix[idx!(pos)]
+                    .is_some()
+                    .then_some(Obstacle::Element(point))
+            })
+    }
+
+    /// Places a new element registered as `name` at `rel_pos` if that cell is empty.
+    /// Returns true if the element was placed
+    ///
+    /// # Arguments
+    ///
+    /// * `rel_pos` - Relative position of the new element
+    /// * `name` - Name of the element in the registry of the simulation
+    pub fn spawn(&mut self, rel_pos: Vector2<isize>, name: &str) -> bool {
+        let Some(pos) = (self.position.cast() + rel_pos).try_cast::<usize>() else {
+            return false;
+        };
+        if !self.in_bounds(rel_pos) || self.matrix[idx!(pos)].is_some() {
+            return false;
+        }
+        let Some(mut element) = self.context.registry.create_by_name(name, self.rng) else {
+            return false;
+        };
+
+        // The new element counts as processed this tick, just like the current one
+        if let Ok(current) = self.try_element() {
+            element.properties.set_visited(current.properties.visited());
+        }
+        self.matrix[idx!(pos)] = Some(element);
+        true
+    }
+
     /// Returns -1 or 1 using `self.rng`
     pub fn rand_dir(&mut self) -> i32 {
-        (self.rng.next_u32() as i32 % 2).abs() * 2 - 1
+        self.range(0..2) as i32 * 2 - 1
+    }
+
+    /// Returns true with the given probability using `self.rng`
+    ///
+    /// # Arguments
+    ///
+    /// * `probability` - Probability between 0 and 1.
+    ///   Values of 0 and below never succeed, values of 1 and above always do
+    pub fn chance(&mut self, probability: f64) -> bool {
+        // 53 random bits fill the mantissa of a f64 in [0, 1)
+        let sample = (self.rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        sample < probability
+    }
+
+    /// Returns a uniformly distributed number in `range` using `self.rng`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty
+    pub fn range(&mut self, range: Range<isize>) -> isize {
+        assert!(!range.is_empty(), "range expects a non empty range");
+        let span = range.end.wrapping_sub(range.start) as usize as u64;
+        range.start.wrapping_add(below(self.rng, span) as isize)
+    }
+
+    /// Returns a random item of `items` using `self.rng` or `None` if `items` is empty
+    pub fn pick<'b, T>(&mut self, items: &'b [T]) -> Option<&'b T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(below(self.rng, items.len() as u64) as usize)
+    }
+
+    /// Returns a random item of `items` where each item is picked proportionally to its weight.
+    /// Returns `None` if `items` is empty or all weights are 0
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - Pairs of an item and its weight
+    pub fn weighted<'b, T>(&mut self, items: &'b [(T, u32)]) -> Option<&'b T> {
+        let total = items.iter().map(|(_, weight)| *weight as u64).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
+
+        let mut sample = below(self.rng, total);
+        items.iter().find_map(|(item, weight)| {
+            if sample < *weight as u64 {
+                Some(item)
+            } else {
+                sample -= *weight as u64;
+                None
+            }
+        })
+    }
+
+    /// Shuffles `items` in place using `self.rng`
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        // Fisher-Yates
+        for i in (1..items.len()).rev() {
+            let j = below(self.rng, i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Returns all eight relative neighbour positions in a random order using `self.rng`
+    pub fn shuffled_neighbours(&mut self) -> [Vector2<isize>; 8] {
+        let mut neighbours = NEIGHBOURS;
+        self.shuffle(&mut neighbours);
+        neighbours
     }
 
     /// Checks if the position is in bounds of `self.matrix`
@@ -103,3 +344,15 @@ impl<'a> ElementApi<'a> {
             && pos.y < self.matrix.nrows() as isize
     }
 }
+
+/// Returns a uniformly distributed number in `0..bound`. `bound` must not be 0
+pub(crate) fn below(rng: &mut SplitMix64, bound: u64) -> u64 {
+    // Lemire's method, rejects samples that would bias the result
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let product = rng.next_u64() as u128 * bound as u128;
+        if product as u64 >= threshold {
+            return (product >> 64) as u64;
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::element_api::ElementApi;
+use crate::elements::behaviour::Behaviour;
+use anyhow::Result;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// Runs behaviours in order until one of them breaks
+#[derive(Clone, Default)]
+pub struct BehaviourChain {
+    behaviours: Vec<Arc<dyn Behaviour>>,
+}
+
+impl BehaviourChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `behaviour` to the end of the chain
+    pub fn then(mut self, behaviour: impl Behaviour + 'static) -> Self {
+        self.behaviours.push(Arc::new(behaviour));
+        self
+    }
+
+    /// Appends an already shared `behaviour` to the end of the chain
+    pub fn then_shared(mut self, behaviour: Arc<dyn Behaviour>) -> Self {
+        self.behaviours.push(behaviour);
+        self
+    }
+}
+
+impl Behaviour for BehaviourChain {
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        for behaviour in &self.behaviours {
+            if behaviour.update(api)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+}
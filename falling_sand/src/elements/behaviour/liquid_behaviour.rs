@@ -1,15 +1,38 @@
 use crate::element_api::ElementApi;
-use crate::{DOWN, LEFT};
-
-#[rustfmt::skip]
-#[allow(clippy::short_circuit_statement)]
-pub fn liquid_behaviour(mut api: ElementApi) {
-    api.flip_visited();
-
-    let dx = api.rand_dir() as isize;
-    let _ = api.swap(DOWN)
-        || api.swap(DOWN + LEFT * dx)
-        || api.swap(DOWN + LEFT * -dx)
-        || api.swap(LEFT * dx)
-        || api.swap(LEFT * -dx);
+use crate::elements::behaviour::Behaviour;
+use anyhow::Result;
+use std::ops::ControlFlow;
+
+/// Falls down and diagonally, otherwise spreads sideways to level out
+#[derive(Clone, Copy, Debug)]
+pub struct LiquidBehaviour {
+    /// Maximum amount of cells the liquid moves sideways per tick
+    pub spread: usize,
+}
+
+impl Default for LiquidBehaviour {
+    fn default() -> Self {
+        LiquidBehaviour { spread: 1 }
+    }
+}
+
+impl Behaviour for LiquidBehaviour {
+    #[rustfmt::skip]
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        let down = api.context.world.gravity;
+        let left = api.context.world.side();
+        let dx = api.rand_dir() as isize;
+        let spread = self.spread as isize;
+        let moved = api.swap(down)
+            || api.swap(down + left * dx)
+            || api.swap(down + left * -dx)
+            || api.move_towards(left * dx * spread, self.spread).moved()
+            || api.move_towards(left * -dx * spread, self.spread).moved();
+
+        if moved {
+            Ok(ControlFlow::Break(()))
+        } else {
+            Ok(ControlFlow::Continue(()))
+        }
+    }
 }
@@ -1,3 +1,19 @@
+use crate::element_api::ElementApi;
+use anyhow::Result;
+use std::ops::ControlFlow;
+
+pub mod chain;
 pub mod liquid_behaviour;
 pub mod powder_behaviour;
 pub mod solid_behaviour;
+
+/// Logic which runs once per tick for every element.
+///
+/// Behaviours can carry configuration and be combined with [`chain::BehaviourChain`].
+/// Returning `ControlFlow::Break` stops the chain, which movement behaviours do after the element moved.
+/// Returning an error stops the chain as well, the simulation logs it and continues with the next cell.
+/// Behaviours should use [`ElementApi::try_element`] over [`ElementApi::element`], because a panic
+/// aborts the whole simulation on wasm
+pub trait Behaviour: Send + Sync {
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>>;
+}
@@ -1,13 +1,26 @@
 use crate::element_api::ElementApi;
-use crate::{DOWN, LEFT};
+use crate::elements::behaviour::Behaviour;
+use anyhow::Result;
+use std::ops::ControlFlow;
 
-#[rustfmt::skip]
-#[allow(clippy::short_circuit_statement)]
-pub fn powder_behaviour(mut api: ElementApi) {
-    api.flip_visited();
+/// Falls down and slides off diagonally to form piles
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PowderBehaviour;
 
-    let dx = api.rand_dir() as isize;
-    let _ = api.swap(DOWN)
-         || api.swap(DOWN + LEFT * dx)
-         || api.swap(DOWN + LEFT * -dx);
+impl Behaviour for PowderBehaviour {
+    #[rustfmt::skip]
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        let down = api.context.world.gravity;
+        let left = api.context.world.side();
+        let dx = api.rand_dir() as isize;
+        let moved = api.swap(down)
+                 || api.swap(down + left * dx)
+                 || api.swap(down + left * -dx);
+
+        if moved {
+            Ok(ControlFlow::Break(()))
+        } else {
+            Ok(ControlFlow::Continue(()))
+        }
+    }
 }
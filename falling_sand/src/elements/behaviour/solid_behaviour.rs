@@ -1,7 +1,19 @@
 use crate::element_api::ElementApi;
-use crate::DOWN;
+use crate::elements::behaviour::Behaviour;
+use anyhow::Result;
+use std::ops::ControlFlow;
 
-pub fn solid_behaviour(mut api: ElementApi) {
-    api.flip_visited();
-    let _ = api.swap(DOWN);
+/// Falls straight down
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SolidBehaviour;
+
+impl Behaviour for SolidBehaviour {
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        let down = api.context.world.gravity;
+        if api.swap(down) {
+            Ok(ControlFlow::Break(()))
+        } else {
+            Ok(ControlFlow::Continue(()))
+        }
+    }
 }
@@ -0,0 +1,280 @@
+use crate::color::Color;
+use crate::element_api::below;
+use crate::elements::behaviour::chain::BehaviourChain;
+use crate::elements::behaviour::liquid_behaviour::LiquidBehaviour;
+use crate::elements::behaviour::powder_behaviour::PowderBehaviour;
+use crate::elements::behaviour::solid_behaviour::SolidBehaviour;
+use crate::elements::behaviour::Behaviour;
+use crate::elements::element::{Element, ElementProperties};
+use crate::elements::registry::{ElementId, ElementRegistry};
+#[cfg(feature = "scripting")]
+use crate::elements::script::{Script, ScriptBehaviour};
+use crate::elements::state::CellState;
+use crate::elements::tags::{Tag, Tags};
+use anyhow::{bail, Context, Result};
+use rand_xoshiro::SplitMix64;
+use ron::extensions::Extensions;
+use ron::Options;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// An element described by data instead of code.
+///
+/// Definition files are RON lists of these, for example
+/// ```ron
+/// [
+///     (
+///         id: 100,
+///         name: "Salt",
+///         color: (240, 240, 235),
+///         color_variation: 10,
+///         swap_priority: 200,
+///         behaviour: [Powder],
+///         tags: [Powder, Soluble],
+///     ),
+/// ]
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ElementDefinition {
+    /// Stable id of the element. The next free id is used if it is missing
+    #[serde(default)]
+    pub id: Option<u16>,
+    pub name: String,
+    /// Red, green and blue
+    pub color: (u8, u8, u8),
+    /// Maximum random brightness offset applied to the color of every new cell.
+    /// The offset is drawn from the rng of the simulation, so seeded runs stay reproducible
+    #[serde(default)]
+    pub color_variation: u8,
+    /// See [`ElementProperties::swap_priority`]
+    pub swap_priority: u8,
+    /// Behaviours which run in order, at least one, see [`BehaviourChain`]
+    pub behaviour: Vec<BehaviourDefinition>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// See [`ElementProperties::emission`]
+    #[serde(default)]
+    pub emission: f32,
+}
+
+/// The builtin behaviours and their parameters
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum BehaviourDefinition {
+    Powder,
+    Liquid {
+        #[serde(default = "default_spread")]
+        spread: usize,
+    },
+    Solid,
+    /// A Rhai script, see `Script`. Requires the `scripting` feature
+    Script(
+        /// Path of the script relative to the definition file
+        PathBuf,
+    ),
+}
+
+fn default_spread() -> usize {
+    LiquidBehaviour::default().spread
+}
+
+impl BehaviourDefinition {
+    #[cfg_attr(not(feature = "scripting"), allow(unused_variables))]
+    fn build(&self, base_dir: &Path, registry: &mut ElementRegistry) -> Result<Arc<dyn Behaviour>> {
+        Ok(match self {
+            BehaviourDefinition::Powder => Arc::new(PowderBehaviour),
+            BehaviourDefinition::Liquid { spread } => {
+                if *spread == 0 {
+                    bail!("Liquid spread has to be at least 1");
+                }
+                Arc::new(LiquidBehaviour { spread: *spread })
+            }
+            BehaviourDefinition::Solid => Arc::new(SolidBehaviour),
+            #[cfg(feature = "scripting")]
+            BehaviourDefinition::Script(path) => {
+                let script = Script::load(base_dir.join(path))?;
+                registry.register_script(Arc::clone(&script));
+                Arc::new(ScriptBehaviour { script })
+            }
+            #[cfg(not(feature = "scripting"))]
+            BehaviourDefinition::Script(path) => {
+                bail!(
+                    "Script {} requires the scripting feature of falling_sand",
+                    path.display()
+                );
+            }
+        })
+    }
+}
+
+impl ElementDefinition {
+    /// Builds a constructor for the defined element
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dir` - Directory paths of scripts are relative to
+    /// * `registry` - Registry which keeps track of loaded scripts
+    pub fn constructor(
+        &self,
+        base_dir: &Path,
+        registry: &mut ElementRegistry,
+    ) -> Result<impl Fn(&mut SplitMix64) -> Element + Send + Sync + 'static> {
+        if self.name.trim().is_empty() {
+            bail!("Element name can't be empty");
+        }
+        if !(0.0..=1.0).contains(&self.emission) {
+            bail!("Emission has to be between 0 and 1");
+        }
+
+        let behaviour = match self.behaviour.as_slice() {
+            [] => bail!("Element needs at least one behaviour"),
+            [single] => single.build(base_dir, registry)?,
+            behaviours => {
+                let mut chain = BehaviourChain::new();
+                for behaviour in behaviours {
+                    chain = chain.then_shared(behaviour.build(base_dir, registry)?);
+                }
+                Arc::new(chain)
+            }
+        };
+        let name: Arc<str> = self.name.as_str().into();
+        let (red, green, blue) = self.color;
+        let color = Color::new(red, green, blue);
+        let color_variation = self.color_variation;
+        let swap_priority = self.swap_priority;
+        let tags = self.tags.iter().copied().collect();
+        let emission = self.emission;
+
+        Ok(move |rng: &mut SplitMix64| Element {
+            properties: Box::new(DefinedProperties {
+                name: Arc::clone(&name),
+                color: vary_color(color, color_variation, rng),
+                visited: false,
+                swap_priority,
+                tags,
+                emission,
+            }),
+            behaviour: Arc::clone(&behaviour),
+        })
+    }
+}
+
+/// Offsets the brightness of `color` by a random amount in `-variation..=variation`
+fn vary_color(color: Color, variation: u8, rng: &mut SplitMix64) -> Color {
+    if variation == 0 {
+        return color;
+    }
+    let offset = below(rng, 2 * variation as u64 + 1) as i16 - variation as i16;
+    let vary = |channel: u8| (channel as i16 + offset).clamp(0, 255) as u8;
+    Color::new_with_alpha(
+        vary(color.red),
+        vary(color.green),
+        vary(color.blue),
+        color.alpha,
+    )
+}
+
+/// Properties of an element created from an [`ElementDefinition`]
+#[derive(Clone)]
+pub struct DefinedProperties {
+    name: Arc<str>,
+    color: Color,
+    visited: bool,
+    swap_priority: u8,
+    tags: Tags,
+    emission: f32,
+}
+
+impl ElementProperties for DefinedProperties {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn visited(&self) -> bool {
+        self.visited
+    }
+
+    fn set_visited(&mut self, value: bool) {
+        self.visited = value;
+    }
+
+    fn swap_priority(&self) -> u8 {
+        self.swap_priority
+    }
+
+    fn tags(&self) -> Tags {
+        self.tags
+    }
+
+    fn emission(&self) -> f32 {
+        self.emission
+    }
+
+    fn state(&self) -> CellState {
+        let mut state = CellState::new();
+        state.insert("color", &self.color);
+        state
+    }
+
+    fn set_state(&mut self, state: &CellState) -> Result<()> {
+        state.read_into("color", &mut self.color)
+    }
+}
+
+/// Parses element definitions in RON format and registers them in `registry`.
+/// Returns the ids of the registered elements. Nothing is registered if any definition is invalid
+///
+/// # Arguments
+///
+/// * `source` - The definitions
+/// * `base_dir` - Directory paths of scripts are relative to
+/// * `registry` - Registry the elements are added to
+pub fn load_definitions(
+    source: &str,
+    base_dir: &Path,
+    registry: &mut ElementRegistry,
+) -> Result<Vec<ElementId>> {
+    let definitions: Vec<ElementDefinition> = Options::default()
+        .with_default_extension(Extensions::IMPLICIT_SOME)
+        .from_str(source)
+        .context("Failed to parse element definitions")?;
+
+    let mut updated = registry.clone();
+    let ids = definitions
+        .iter()
+        .map(|definition| {
+            let id = definition
+                .id
+                .map_or_else(|| updated.next_free_id(), ElementId);
+            definition
+                .constructor(base_dir, &mut updated)
+                .and_then(|constructor| updated.register_with_rng(id, constructor))
+                .with_context(|| format!("Invalid definition of element {:?}", definition.name))?;
+            Ok(id)
+        })
+        .collect::<Result<_>>()?;
+
+    *registry = updated;
+    Ok(ids)
+}
+
+/// Reads a RON file of element definitions and registers them in `registry`.
+/// Returns the ids of the registered elements
+pub fn load_definitions_file(
+    path: impl AsRef<Path>,
+    registry: &mut ElementRegistry,
+) -> Result<Vec<ElementId>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Couldnt read element definitions {}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    load_definitions(&source, base_dir, registry)
+        .with_context(|| format!("Couldnt load element definitions {}", path.display()))
+}
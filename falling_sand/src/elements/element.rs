@@ -1,6 +1,11 @@
 use crate::color::Color;
-use crate::element_api::ElementApi;
+use crate::elements::behaviour::Behaviour;
+use crate::elements::state::CellState;
+use crate::elements::tags::Tags;
+use anyhow::Result;
 use dyn_clone::{clone_trait_object, DynClone};
+pub use element_derive::ElementProperties;
+use std::sync::Arc;
 
 pub trait ElementProperties: DynClone {
     fn name(&self) -> &str;
@@ -16,11 +21,32 @@ pub trait ElementProperties: DynClone {
     /// Around 127 is typical for a liquid
     /// Around 212 is typical for a solid
     fn swap_priority(&self) -> u8;
+
+    /// Properties behaviours can check for, like being a liquid or flammable
+    fn tags(&self) -> Tags {
+        Tags::empty()
+    }
+
+    /// How strongly the element glows, from 0 for not at all to 1.
+    /// Renderers may draw the glow in the color of the element
+    fn emission(&self) -> f32 {
+        0.0
+    }
+
+    /// State of this cell which is kept when the element is saved, like its varied color
+    fn state(&self) -> CellState {
+        CellState::new()
+    }
+
+    /// Restores state returned by [`ElementProperties::state`]. Missing keys keep their current value
+    fn set_state(&mut self, _state: &CellState) -> Result<()> {
+        Ok(())
+    }
 }
 clone_trait_object!(ElementProperties);
 
 #[derive(Clone)]
 pub struct Element {
     pub properties: Box<dyn ElementProperties + Sync + Send>,
-    pub behaviour: fn(api: ElementApi),
+    pub behaviour: Arc<dyn Behaviour>,
 }
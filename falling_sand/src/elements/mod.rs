@@ -1,5 +1,13 @@
 pub mod behaviour;
+pub mod definition;
 pub mod element;
+pub mod registry;
 pub mod sand;
+#[cfg(feature = "serde")]
+pub mod saved;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod state;
 pub mod stone;
+pub mod tags;
 pub mod water;
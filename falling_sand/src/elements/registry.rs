@@ -0,0 +1,216 @@
+use crate::color::Color;
+use crate::elements::element::Element;
+use crate::elements::sand::new_sand;
+#[cfg(feature = "scripting")]
+use crate::elements::script::Script;
+use crate::elements::stone::new_stone;
+use crate::elements::tags::{Category, Tags};
+use crate::elements::water::new_water;
+use anyhow::{bail, Result};
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Stable numeric identifier of a registered element
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct ElementId(pub u16);
+
+impl ElementId {
+    /// Reserved for empty cells, can't be registered
+    pub const EMPTY: ElementId = ElementId(0);
+    pub const SAND: ElementId = ElementId(1);
+    pub const WATER: ElementId = ElementId(2);
+    pub const STONE: ElementId = ElementId(3);
+}
+
+impl fmt::Display for ElementId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Constructs an element, randomness like color variation is drawn from the given rng
+pub type ElementConstructor = Arc<dyn Fn(&mut SplitMix64) -> Element + Send + Sync>;
+
+/// An element constructor together with its id and name
+#[derive(Clone)]
+pub struct RegisteredElement {
+    pub id: ElementId,
+    pub name: String,
+    pub tags: Tags,
+    /// Color of a newly constructed instance, which can vary between instances
+    pub color: Color,
+    constructor: ElementConstructor,
+}
+
+impl RegisteredElement {
+    /// Constructs a new instance of the element
+    pub fn create(&self, rng: &mut SplitMix64) -> Element {
+        (self.constructor)(rng)
+    }
+
+    /// Returns the palette category of the element
+    pub fn category(&self) -> Category {
+        self.tags.category()
+    }
+}
+
+/// Collection of all elements available to a simulation, addressable by id and name
+#[derive(Clone, Default)]
+pub struct ElementRegistry {
+    elements: Vec<RegisteredElement>,
+    by_id: HashMap<ElementId, usize>,
+    by_name: HashMap<String, usize>,
+    #[cfg(feature = "scripting")]
+    scripts: Vec<Arc<Script>>,
+}
+
+impl ElementRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry containing sand, water and stone
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for (id, constructor) in [
+            (ElementId::SAND, new_sand as fn() -> Element),
+            (ElementId::WATER, new_water),
+            (ElementId::STONE, new_stone),
+        ] {
+            registry
+                .register(id, constructor)
+                .expect("Builtin elements have unique ids and names");
+        }
+        registry
+    }
+
+    /// Registers `constructor` under `id`. The name is taken from the constructed element.
+    /// Fails if the id is reserved or the id or name is already taken
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Stable id of the element, should never change once the element is published
+    /// * `constructor` - Function constructing a new instance of the element
+    pub fn register(
+        &mut self,
+        id: ElementId,
+        constructor: impl Fn() -> Element + Send + Sync + 'static,
+    ) -> Result<()> {
+        self.register_with_rng(id, move |_: &mut SplitMix64| constructor())
+    }
+
+    /// Like [`register`](Self::register), for constructors which need randomness.
+    /// The registered color is taken from an instance constructed with a fixed seed
+    pub fn register_with_rng(
+        &mut self,
+        id: ElementId,
+        constructor: impl Fn(&mut SplitMix64) -> Element + Send + Sync + 'static,
+    ) -> Result<()> {
+        let element = constructor(&mut SplitMix64::seed_from_u64(0));
+        let name = element.properties.name().to_string();
+        let tags = element.properties.tags();
+        let color = element.properties.color();
+        if id == ElementId::EMPTY {
+            bail!("Element id {id} is reserved for empty cells");
+        }
+        if let Some(other) = self.get(id) {
+            bail!(
+                "Can't register {name}, id {id} is already used by {}",
+                other.name
+            );
+        }
+        if self.by_name.contains_key(&name) {
+            bail!("Can't register {name} with id {id}, the name is already used");
+        }
+
+        self.by_id.insert(id, self.elements.len());
+        self.by_name.insert(name.clone(), self.elements.len());
+        self.elements.push(RegisteredElement {
+            id,
+            name,
+            tags,
+            color,
+            constructor: Arc::new(constructor),
+        });
+        Ok(())
+    }
+
+    /// Returns the smallest id bigger than all registered ids
+    pub fn next_free_id(&self) -> ElementId {
+        self.by_id
+            .keys()
+            .max()
+            .map_or(ElementId(1), |id| ElementId(id.0.saturating_add(1)))
+    }
+
+    pub fn get(&self, id: ElementId) -> Option<&RegisteredElement> {
+        self.by_id.get(&id).map(|&i| &self.elements[i])
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&RegisteredElement> {
+        self.by_name.get(name).map(|&i| &self.elements[i])
+    }
+
+    /// Returns the id `element` is registered under, looked up by its name
+    pub fn id_of(&self, element: &Element) -> Option<ElementId> {
+        self.get_by_name(element.properties.name()).map(|x| x.id)
+    }
+
+    /// Constructs a new instance of the element registered under `id`
+    pub fn create(&self, id: ElementId, rng: &mut SplitMix64) -> Option<Element> {
+        self.get(id).map(|x| x.create(rng))
+    }
+
+    /// Constructs a new instance of the element registered under `name`
+    pub fn create_by_name(&self, name: &str, rng: &mut SplitMix64) -> Option<Element> {
+        self.get_by_name(name).map(|x| x.create(rng))
+    }
+
+    /// Iterates over all elements in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredElement> {
+        self.elements.iter()
+    }
+
+    /// Returns all elements grouped by category, each group in registration order
+    pub fn by_category(&self) -> Vec<(Category, Vec<&RegisteredElement>)> {
+        let mut groups: Vec<(Category, Vec<&RegisteredElement>)> = Vec::new();
+        for element in &self.elements {
+            let category = element.category();
+            match groups.iter_mut().find(|(x, _)| *x == category) {
+                Some((_, group)) => group.push(element),
+                None => groups.push((category, vec![element])),
+            }
+        }
+        groups.sort_by_key(|(category, _)| *category);
+        groups
+    }
+
+    /// Remembers a script used by registered elements, so it can be reloaded through [`ElementRegistry::scripts`]
+    #[cfg(feature = "scripting")]
+    pub fn register_script(&mut self, script: Arc<Script>) {
+        self.scripts.push(script);
+    }
+
+    /// Returns the scripts used by registered elements
+    #[cfg(feature = "scripting")]
+    pub fn scripts(&self) -> &[Arc<Script>] {
+        &self.scripts
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
@@ -1,47 +1,16 @@
 use crate::color::Color;
-use crate::elements::behaviour::powder_behaviour::powder_behaviour;
-use crate::elements::element::{Element, ElementProperties};
-
-#[derive(Clone)]
+use crate::elements::behaviour::powder_behaviour::PowderBehaviour;
+use crate::elements::element::ElementProperties;
+
+#[derive(Clone, ElementProperties)]
+#[element(
+    name = "Sand",
+    color = (239, 221, 111),
+    swap_priority = 212,
+    behaviour = PowderBehaviour,
+    tags = [Powder],
+)]
 pub struct SandProperties {
     color: Color,
     visited: bool,
 }
-
-impl ElementProperties for SandProperties {
-    fn name(&self) -> &str {
-        "Sand"
-    }
-
-    fn color(&self) -> Color {
-        self.color
-    }
-
-    fn visited(&self) -> bool {
-        self.visited
-    }
-
-    fn set_visited(&mut self, value: bool) {
-        self.visited = value;
-    }
-
-    fn swap_priority(&self) -> u8 {
-        212
-    }
-}
-
-impl Default for SandProperties {
-    fn default() -> Self {
-        SandProperties {
-            color: Color::new(239, 221, 111),
-            visited: false,
-        }
-    }
-}
-
-pub fn new_sand() -> Element {
-    Element {
-        properties: Box::<SandProperties>::default(),
-        behaviour: powder_behaviour,
-    }
-}
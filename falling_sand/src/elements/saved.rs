@@ -0,0 +1,93 @@
+use crate::elements::element::Element;
+use crate::elements::registry::{ElementId, ElementRegistry};
+use crate::elements::state::CellState;
+use anyhow::{anyhow, Context, Result};
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use serde::de::{DeserializeSeed, Deserializer, Error as _};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Serializable form of an [`Element`]. The kind of element is stored by name and id
+/// and resolved through an [`ElementRegistry`] when the element is restored
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedElement {
+    /// Only known if the element was saved with a registry
+    #[serde(default)]
+    pub id: Option<ElementId>,
+    pub name: String,
+    /// See [`ElementProperties::state`](crate::elements::element::ElementProperties::state)
+    #[serde(default)]
+    pub state: CellState,
+}
+
+impl SavedElement {
+    /// Saves `element` together with the id it is registered under in `registry`
+    pub fn new(element: &Element, registry: &ElementRegistry) -> Self {
+        SavedElement {
+            id: registry.id_of(element),
+            ..SavedElement::from(element)
+        }
+    }
+
+    /// Constructs the saved element and restores its cell state.
+    /// The element is looked up by name first, so saves stay valid if ids of custom elements change.
+    /// The id is used if the element was renamed
+    pub fn restore(&self, registry: &ElementRegistry) -> Result<Element> {
+        let registered = registry
+            .get_by_name(&self.name)
+            .or_else(|| self.id.and_then(|id| registry.get(id)))
+            .ok_or_else(|| match self.id {
+                Some(id) => anyhow!("Unknown element {:?} with id {id}", self.name),
+                None => anyhow!("Unknown element {:?}", self.name),
+            })?;
+        if registered.name != self.name {
+            log::warn!(
+                "Loading element {:?} as {:?} registered under the same id",
+                self.name,
+                registered.name
+            );
+        }
+
+        // The saved state replaces whatever was random about the new instance, so a fixed seed
+        // keeps restoring from consuming the rng of the simulation
+        let mut element = registered.create(&mut SplitMix64::seed_from_u64(0));
+        element
+            .properties
+            .set_state(&self.state)
+            .with_context(|| format!("Invalid state of element {:?}", self.name))?;
+        Ok(element)
+    }
+}
+
+impl From<&Element> for SavedElement {
+    fn from(element: &Element) -> Self {
+        SavedElement {
+            id: None,
+            name: element.properties.name().to_string(),
+            state: element.properties.state(),
+        }
+    }
+}
+
+/// Elements serialize as [`SavedElement`] without id. Use [`ElementSeed`] to deserialize them
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedElement::from(self).serialize(serializer)
+    }
+}
+
+/// Deserializes an [`Element`] saved as [`SavedElement`] by resolving it in `registry`
+#[derive(Clone, Copy)]
+pub struct ElementSeed<'a> {
+    pub registry: &'a ElementRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for ElementSeed<'_> {
+    type Value = Element;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Element, D::Error> {
+        SavedElement::deserialize(deserializer)?
+            .restore(self.registry)
+            .map_err(|err| D::Error::custom(format!("{err:#}")))
+    }
+}
@@ -0,0 +1,269 @@
+use crate::element_api::ElementApi;
+use crate::elements::behaviour::Behaviour;
+use crate::elements::registry::ElementRegistry;
+use crate::elements::tags::Tag;
+use crate::simulation::{ElementMatrix, TickContext, WorldParameters};
+use anyhow::{anyhow, Context, Result};
+use nalgebra::{Dyn, VecStorage, Vector2};
+use rand_xoshiro::SplitMix64;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST, FLOAT, INT};
+use std::fs;
+use std::mem;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::SystemTime;
+
+/// Upper bound of operations per script call, stops endless loops from freezing the simulation
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A compiled Rhai behaviour script which can be reloaded while the simulation runs.
+///
+/// A script defines `fn update(api)`, which is called once per tick for every element using it.
+/// Returning `true` stops following behaviours, like `ControlFlow::Break` does.
+/// `api` offers the following methods, positions are relative to the element:
+///
+/// * `swap(x, y)`, `move_towards(x, y, max_steps)` - Move the element, return true if it moved
+/// * `is_empty(x, y)`, `in_bounds(x, y)` - Query neighbours
+/// * `name_at(x, y)` - Name of the element at the position or an empty string
+/// * `has_tag(x, y, tag)` - Whether the element at the position has a tag like `"Liquid"`
+/// * `spawn_element(x, y, name)` - Place a new element into an empty cell
+/// * `rand_dir()`, `chance(probability)`, `range(start, end)` - Seeded randomness
+/// * `tick`, `gravity_x`, `gravity_y`, `ambient_temperature` - Simulation state
+pub struct Script {
+    engine: Engine,
+    ast: RwLock<AST>,
+    path: Option<PathBuf>,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl Script {
+    /// Compiles `source` into a script
+    pub fn compile(source: &str) -> Result<Arc<Self>> {
+        let engine = script_engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow!("Failed to compile script: {err}"))?;
+        Ok(Arc::new(Script {
+            engine,
+            ast: RwLock::new(ast),
+            path: None,
+            modified: Mutex::new(None),
+        }))
+    }
+
+    /// Reads and compiles the script at `path`. The script can be reloaded with [`Script::reload_if_changed`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Arc<Self>> {
+        let path = path.as_ref();
+        let engine = script_engine();
+        let (ast, modified) = Self::read(&engine, path)?;
+        Ok(Arc::new(Script {
+            engine,
+            ast: RwLock::new(ast),
+            path: Some(path.to_path_buf()),
+            modified: Mutex::new(modified),
+        }))
+    }
+
+    /// Returns the file the script was loaded from
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Recompiles the script if its file was modified since it was last read.
+    /// Returns true if the script was reloaded. The previous version stays active if compiling fails
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let modified = fs::metadata(path).and_then(|x| x.modified()).ok();
+        let mut last_modified = self.modified.lock().unwrap_or_else(PoisonError::into_inner);
+        if modified == *last_modified {
+            return Ok(false);
+        }
+
+        // Remember the modification time even if compiling fails, so the error is only reported once
+        *last_modified = modified;
+        let (ast, _) = Self::read(&self.engine, path)?;
+        *self.ast.write().unwrap_or_else(PoisonError::into_inner) = ast;
+        Ok(true)
+    }
+
+    fn read(engine: &Engine, path: &Path) -> Result<(AST, Option<SystemTime>)> {
+        let modified = fs::metadata(path).and_then(|x| x.modified()).ok();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Couldnt read script {}", path.display()))?;
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow!("Failed to compile script {}: {err}", path.display()))?;
+        Ok((ast, modified))
+    }
+
+    fn update(&self, api: ScriptApi) -> Result<bool> {
+        let ast = self.ast.read().unwrap_or_else(PoisonError::into_inner);
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &ast, "update", (api,))
+            .map_err(|err| anyhow!("{err}"))?;
+        Ok(result.as_bool().unwrap_or(false))
+    }
+}
+
+/// Runs a [`Script`] as behaviour
+#[derive(Clone)]
+pub struct ScriptBehaviour {
+    pub script: Arc<Script>,
+}
+
+impl Behaviour for ScriptBehaviour {
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        // Rhai only accepts 'static values, so the state is moved into the script api and back afterwards
+        let script_api = ScriptApi(Arc::new(Mutex::new(ScriptState {
+            matrix: mem::replace(api.matrix, empty_matrix()),
+            rng: api.rng.clone(),
+            tick: api.context.tick,
+            world: api.context.world.clone(),
+            registry: Arc::clone(api.context.registry),
+            position: api.position,
+        })));
+        let restore = RestoreOnDrop {
+            api,
+            script_api: script_api.clone(),
+        };
+        let result = self.script.update(script_api);
+        drop(restore);
+
+        if result.context("Script failed")? {
+            Ok(ControlFlow::Break(()))
+        } else {
+            Ok(ControlFlow::Continue(()))
+        }
+    }
+}
+
+/// Replacing the matrix with an empty one doesn't allocate
+fn empty_matrix() -> ElementMatrix {
+    ElementMatrix::from_data(VecStorage::new(Dyn(0), Dyn(0), Vec::new()))
+}
+
+/// Moves the state of `script_api` back into `api` when dropped,
+/// so the matrix is restored even if the script panics
+struct RestoreOnDrop<'a, 'b> {
+    api: &'a mut ElementApi<'b>,
+    script_api: ScriptApi,
+}
+
+impl Drop for RestoreOnDrop<'_, '_> {
+    fn drop(&mut self) {
+        let mut state = self.script_api.lock();
+        *self.api.matrix = mem::replace(&mut state.matrix, empty_matrix());
+        *self.api.rng = state.rng.clone();
+        self.api.position = state.position;
+    }
+}
+
+struct ScriptState {
+    matrix: ElementMatrix,
+    rng: SplitMix64,
+    tick: u64,
+    world: WorldParameters,
+    registry: Arc<ElementRegistry>,
+    position: Vector2<usize>,
+}
+
+/// The `api` value scripts receive
+#[derive(Clone)]
+struct ScriptApi(Arc<Mutex<ScriptState>>);
+
+impl ScriptApi {
+    fn lock(&self) -> std::sync::MutexGuard<'_, ScriptState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs `f` with an ElementApi of the moved state
+    fn with<R>(&mut self, f: impl FnOnce(&mut ElementApi) -> R) -> R {
+        let mut state = self.lock();
+        let state = &mut *state;
+        let context = TickContext {
+            tick: state.tick,
+            world: &state.world,
+            registry: &state.registry,
+        };
+        let mut api = ElementApi::new(&mut state.matrix, &mut state.rng, context, state.position);
+        let result = f(&mut api);
+        state.position = api.position;
+        result
+    }
+}
+
+fn rel(x: INT, y: INT) -> Vector2<isize> {
+    Vector2::new(x as isize, y as isize)
+}
+
+fn script_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine
+        .register_type_with_name::<ScriptApi>("Api")
+        .register_fn("swap", |api: &mut ScriptApi, x: INT, y: INT| {
+            api.with(|api| api.swap(rel(x, y)))
+        })
+        .register_fn(
+            "move_towards",
+            |api: &mut ScriptApi, x: INT, y: INT, max_steps: INT| {
+                api.with(|api| {
+                    api.move_towards(rel(x, y), max_steps.max(0) as usize)
+                        .moved()
+                })
+            },
+        )
+        .register_fn("in_bounds", |api: &mut ScriptApi, x: INT, y: INT| {
+            api.with(|api| api.in_bounds(rel(x, y)))
+        })
+        .register_fn("is_empty", |api: &mut ScriptApi, x: INT, y: INT| {
+            api.with(|api| api.in_bounds(rel(x, y)) && api.other_element(rel(x, y)).is_none())
+        })
+        .register_fn("name_at", |api: &mut ScriptApi, x: INT, y: INT| {
+            api.with(|api| {
+                api.other_element(rel(x, y))
+                    .map(|x| x.properties.name().to_string())
+                    .unwrap_or_default()
+            })
+        })
+        .register_fn(
+            "has_tag",
+            |api: &mut ScriptApi, x: INT, y: INT, tag: &str| -> Result<bool, Box<EvalAltResult>> {
+                let tag = tag.parse::<Tag>().map_err(|err| err.to_string())?;
+                Ok(api.with(|api| api.has_tag(rel(x, y), tag)))
+            },
+        )
+        .register_fn(
+            "spawn_element",
+            |api: &mut ScriptApi, x: INT, y: INT, name: &str| {
+                api.with(|api| api.spawn(rel(x, y), name))
+            },
+        )
+        .register_fn("rand_dir", |api: &mut ScriptApi| {
+            api.with(|api| api.rand_dir() as INT)
+        })
+        .register_fn("chance", |api: &mut ScriptApi, probability: FLOAT| {
+            api.with(|api| api.chance(probability))
+        })
+        .register_fn("range", |api: &mut ScriptApi, start: INT, end: INT| {
+            if start >= end {
+                return start;
+            }
+            api.with(|api| api.range(start as isize..end as isize) as INT)
+        })
+        .register_get("tick", |api: &mut ScriptApi| api.lock().tick as INT)
+        .register_get("gravity_x", |api: &mut ScriptApi| {
+            api.lock().world.gravity.x as INT
+        })
+        .register_get("gravity_y", |api: &mut ScriptApi| {
+            api.lock().world.gravity.y as INT
+        })
+        .register_get("ambient_temperature", |api: &mut ScriptApi| {
+            api.lock().world.ambient_temperature as FLOAT
+        });
+    engine
+}
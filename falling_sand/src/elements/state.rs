@@ -0,0 +1,146 @@
+use crate::color::Color;
+use anyhow::{anyhow, Context, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A single value of a [`CellState`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StateValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Color(Color),
+    Text(String),
+}
+
+/// Per-cell state of an element which differs between elements of the same kind,
+/// like a varied color, a lifetime or a temperature. Keys are field names
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct CellState(BTreeMap<String, StateValue>);
+
+impl CellState {
+    pub fn new() -> Self {
+        CellState::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: &impl CellValue) {
+        self.0.insert(key.to_string(), value.to_state());
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<StateValue> {
+        self.0.remove(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&StateValue> {
+        self.0.get(key)
+    }
+
+    /// Overwrites `value` with the value stored under `key`. Missing keys leave `value` unchanged
+    pub fn read_into<T: CellValue>(&self, key: &str, value: &mut T) -> Result<()> {
+        if let Some(state) = self.0.get(key) {
+            *value = T::from_state(state).with_context(|| format!("Invalid value of {key:?}"))?;
+        }
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &StateValue)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Types which can be stored in a [`CellState`]
+pub trait CellValue: Sized {
+    fn to_state(&self) -> StateValue;
+    fn from_state(state: &StateValue) -> Result<Self>;
+}
+
+impl CellValue for bool {
+    fn to_state(&self) -> StateValue {
+        StateValue::Bool(*self)
+    }
+
+    fn from_state(state: &StateValue) -> Result<Self> {
+        match state {
+            StateValue::Bool(value) => Ok(*value),
+            other => Err(anyhow!("Expected a bool, got {other:?}")),
+        }
+    }
+}
+
+macro_rules! int_cell_value {
+    ($($int:ty),*) => {
+        $(
+            impl CellValue for $int {
+                fn to_state(&self) -> StateValue {
+                    StateValue::Int(*self as i64)
+                }
+
+                fn from_state(state: &StateValue) -> Result<Self> {
+                    match state {
+                        StateValue::Int(value) => <$int>::try_from(*value)
+                            .with_context(|| format!("{value} is out of range")),
+                        other => Err(anyhow!("Expected an integer, got {other:?}")),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+int_cell_value!(u8, u16, u32, u64, i8, i16, i32, i64, usize, isize);
+
+macro_rules! float_cell_value {
+    ($($float:ty),*) => {
+        $(
+            impl CellValue for $float {
+                fn to_state(&self) -> StateValue {
+                    StateValue::Float(*self as f64)
+                }
+
+                fn from_state(state: &StateValue) -> Result<Self> {
+                    match state {
+                        StateValue::Float(value) => Ok(*value as $float),
+                        StateValue::Int(value) => Ok(*value as $float),
+                        other => Err(anyhow!("Expected a float, got {other:?}")),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+float_cell_value!(f32, f64);
+
+impl CellValue for Color {
+    fn to_state(&self) -> StateValue {
+        StateValue::Color(*self)
+    }
+
+    fn from_state(state: &StateValue) -> Result<Self> {
+        match state {
+            StateValue::Color(value) => Ok(*value),
+            other => Err(anyhow!("Expected a color, got {other:?}")),
+        }
+    }
+}
+
+impl CellValue for String {
+    fn to_state(&self) -> StateValue {
+        StateValue::Text(self.clone())
+    }
+
+    fn from_state(state: &StateValue) -> Result<Self> {
+        match state {
+            StateValue::Text(value) => Ok(value.clone()),
+            other => Err(anyhow!("Expected text, got {other:?}")),
+        }
+    }
+}
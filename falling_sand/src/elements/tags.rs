@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// A property of an element which behaviours can check for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Tag {
+    Liquid,
+    Powder,
+    Gas,
+    Solid,
+    /// Never moves on its own
+    Static,
+    Flammable,
+    Conductive,
+    Soluble,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 8] = [
+        Tag::Liquid,
+        Tag::Powder,
+        Tag::Gas,
+        Tag::Solid,
+        Tag::Static,
+        Tag::Flammable,
+        Tag::Conductive,
+        Tag::Soluble,
+    ];
+
+    const fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tag::ALL
+            .into_iter()
+            .find(|tag| tag.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow!("Unknown tag {s:?}"))
+    }
+}
+
+/// A set of tags stored as bit flags, so checking for a tag is cheap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tags(u16);
+
+impl Tags {
+    pub const fn empty() -> Self {
+        Tags(0)
+    }
+
+    pub const fn new(tags: &[Tag]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < tags.len() {
+            bits |= tags[i].bit();
+            i += 1;
+        }
+        Tags(bits)
+    }
+
+    pub const fn contains(self, tag: Tag) -> bool {
+        self.0 & tag.bit() != 0
+    }
+
+    pub fn insert(&mut self, tag: Tag) {
+        self.0 |= tag.bit();
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Tag> {
+        Tag::ALL.into_iter().filter(move |&tag| self.contains(tag))
+    }
+
+    /// Returns the category elements with these tags are listed under in palettes
+    pub fn category(self) -> Category {
+        [
+            (Tag::Powder, Category::Powder),
+            (Tag::Liquid, Category::Liquid),
+            (Tag::Gas, Category::Gas),
+            (Tag::Solid, Category::Solid),
+            (Tag::Static, Category::Solid),
+        ]
+        .into_iter()
+        .find_map(|(tag, category)| self.contains(tag).then_some(category))
+        .unwrap_or(Category::Other)
+    }
+}
+
+impl FromIterator<Tag> for Tags {
+    fn from_iter<T: IntoIterator<Item = Tag>>(iter: T) -> Self {
+        let mut tags = Tags::empty();
+        for tag in iter {
+            tags.insert(tag);
+        }
+        tags
+    }
+}
+
+/// Groups elements in palettes, in the order they are listed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    Powder,
+    Liquid,
+    Gas,
+    Solid,
+    Other,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,254 @@
+use crate::color::Color;
+use crate::elements::registry::ElementRegistry;
+use crate::image::raster::{rasterize, RasterOptions, RgbaImage};
+use crate::render::TICK_INTERVAL;
+use crate::simulation::Simulation;
+use anyhow::{ensure, Context, Result};
+use png::{BitDepth, ColorType, Encoder};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// Animated PNG
+    Apng,
+    /// Requires the `gif` feature
+    Gif,
+}
+
+impl AnimationFormat {
+    /// Guesses the format from the extension of `path`, defaulting to APNG
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gif") => AnimationFormat::Gif,
+            _ => AnimationFormat::Apng,
+        }
+    }
+}
+
+/// Settings of [`record_animation`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationOptions {
+    pub format: AnimationFormat,
+    /// Amount of ticks between two frames, 1 records every tick
+    pub frame_skip: u32,
+    /// Scale and background of the frames
+    pub raster: RasterOptions,
+    /// How long a tick is shown, the delay between frames is `frame_skip` times this.
+    /// Defaults to [`TICK_INTERVAL`], so animations play as fast as the interactive front ends
+    pub tick_duration: Duration,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions {
+            format: AnimationFormat::Apng,
+            frame_skip: 1,
+            raster: RasterOptions::default(),
+            tick_duration: TICK_INTERVAL,
+        }
+    }
+}
+
+/// Up to 256 colors frames are quantized to, made of the background and the colors of all registered elements
+struct QuantizedPalette {
+    colors: Vec<Color>,
+    /// Index of every color seen so far
+    cache: HashMap<[u8; 4], u8>,
+}
+
+impl QuantizedPalette {
+    fn new(registry: &ElementRegistry, background: Color) -> Self {
+        let mut colors = vec![background];
+        for element in registry.iter() {
+            if colors.len() == 256 {
+                log::warn!("More than 255 elements registered, some colors are approximated");
+                break;
+            }
+            if !colors.contains(&element.color) {
+                colors.push(element.color);
+            }
+        }
+        QuantizedPalette {
+            colors,
+            cache: HashMap::new(),
+        }
+    }
+
+    fn index_of(&mut self, pixel: [u8; 4]) -> u8 {
+        let colors = &self.colors;
+        *self.cache.entry(pixel).or_insert_with(|| {
+            let distance = |color: &Color| {
+                [color.red, color.green, color.blue, color.alpha]
+                    .into_iter()
+                    .zip(pixel)
+                    .map(|(a, b)| (a as i32 - b as i32).pow(2))
+                    .sum::<i32>()
+            };
+            (0..colors.len())
+                .min_by_key(|&i| distance(&colors[i]))
+                .unwrap_or(0) as u8
+        })
+    }
+
+    fn indices(&mut self, image: &RgbaImage) -> Vec<u8> {
+        image
+            .pixels
+            .chunks_exact(4)
+            .map(|pixel| self.index_of([pixel[0], pixel[1], pixel[2], pixel[3]]))
+            .collect()
+    }
+
+    fn rgb(&self) -> Vec<u8> {
+        self.colors
+            .iter()
+            .flat_map(|x| [x.red, x.green, x.blue])
+            .collect()
+    }
+
+    fn alpha(&self) -> Vec<u8> {
+        self.colors.iter().map(|x| x.alpha).collect()
+    }
+}
+
+/// Writes frames as they are recorded
+enum FrameWriter<W: Write> {
+    Apng(png::Writer<W>),
+    #[cfg(feature = "gif")]
+    Gif(gif::Encoder<W>, u16),
+}
+
+impl<W: Write> FrameWriter<W> {
+    fn new(
+        writer: W,
+        options: &AnimationOptions,
+        palette: &QuantizedPalette,
+        size: (u32, u32),
+        frames: u32,
+    ) -> Result<Self> {
+        let delay = options.tick_duration * options.frame_skip;
+        match options.format {
+            AnimationFormat::Apng => {
+                let mut encoder = Encoder::new(writer, size.0, size.1);
+                encoder.set_color(ColorType::Indexed);
+                encoder.set_depth(BitDepth::Eight);
+                encoder.set_palette(palette.rgb());
+                encoder.set_trns(palette.alpha());
+                encoder.set_animated(frames, 0)?;
+                let millis = u16::try_from(delay.as_millis()).unwrap_or(u16::MAX);
+                encoder.set_frame_delay(millis, 1000)?;
+                let writer = encoder
+                    .write_header()
+                    .context("Failed to write APNG header")?;
+                Ok(FrameWriter::Apng(writer))
+            }
+            #[cfg(feature = "gif")]
+            AnimationFormat::Gif => {
+                let width =
+                    u16::try_from(size.0).context("GIF frames are limited to 65535 pixels")?;
+                let height =
+                    u16::try_from(size.1).context("GIF frames are limited to 65535 pixels")?;
+                let mut encoder = gif::Encoder::new(writer, width, height, &palette.rgb())
+                    .context("Failed to write GIF header")?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                // GIF delays are in hundredths of a second
+                let delay = u16::try_from(delay.as_millis() / 10).unwrap_or(u16::MAX);
+                Ok(FrameWriter::Gif(encoder, delay))
+            }
+            #[cfg(not(feature = "gif"))]
+            AnimationFormat::Gif => {
+                anyhow::bail!("GIF export requires the gif feature of falling_sand")
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "gif"), allow(unused_variables))]
+    fn write_frame(
+        &mut self,
+        indices: &[u8],
+        size: (u32, u32),
+        palette: &QuantizedPalette,
+    ) -> Result<()> {
+        match self {
+            FrameWriter::Apng(writer) => writer
+                .write_image_data(indices)
+                .context("Failed to write APNG frame"),
+            #[cfg(feature = "gif")]
+            FrameWriter::Gif(encoder, delay) => {
+                let mut frame = gif::Frame {
+                    width: size.0 as u16,
+                    height: size.1 as u16,
+                    delay: *delay,
+                    buffer: indices.into(),
+                    ..gif::Frame::default()
+                };
+                frame.transparent = palette
+                    .colors
+                    .iter()
+                    .position(|x| x.alpha == 0)
+                    .map(|x| x as u8);
+                encoder
+                    .write_frame(&frame)
+                    .context("Failed to write GIF frame")
+            }
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            FrameWriter::Apng(writer) => writer.finish().context("Failed to finish APNG"),
+            #[cfg(feature = "gif")]
+            FrameWriter::Gif(encoder, _) => {
+                encoder
+                    .into_inner()
+                    .context("Failed to finish GIF")?
+                    .flush()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Ticks `simulation` `ticks` times and records it as animation.
+/// The first frame shows the simulation before the first tick,
+/// then a frame is recorded every `options.frame_skip` ticks
+pub fn record_animation(
+    simulation: &mut Simulation,
+    ticks: u64,
+    options: &AnimationOptions,
+    writer: impl Write,
+) -> Result<()> {
+    ensure!(options.frame_skip > 0, "Frame skip has to be at least 1");
+    let frames = u32::try_from(ticks / options.frame_skip as u64 + 1).context("Too many frames")?;
+
+    let mut palette = QuantizedPalette::new(&simulation.registry, options.raster.background);
+    let first = rasterize(&simulation.matrix, &options.raster)?;
+    let size = (first.width, first.height);
+    let mut writer = FrameWriter::new(writer, options, &palette, size, frames)?;
+    writer.write_frame(&palette.indices(&first), size, &palette)?;
+
+    for tick in 1..=ticks {
+        simulation.tick();
+        if tick % options.frame_skip as u64 == 0 {
+            let frame = rasterize(&simulation.matrix, &options.raster)?;
+            writer.write_frame(&palette.indices(&frame), size, &palette)?;
+        }
+    }
+    writer.finish()
+}
+
+/// Records an animation to the file at `path`, see [`record_animation`]
+pub fn record_animation_file(
+    simulation: &mut Simulation,
+    ticks: u64,
+    options: &AnimationOptions,
+    path: impl AsRef<Path>,
+) -> Result<()> {
+    let path = path.as_ref();
+    let file = File::create(path).with_context(|| format!("Couldnt create {}", path.display()))?;
+    record_animation(simulation, ticks, options, BufWriter::new(file))
+        .with_context(|| format!("Couldnt record animation {}", path.display()))
+}
@@ -0,0 +1,195 @@
+use crate::color::Color;
+use crate::elements::registry::{ElementId, ElementRegistry};
+use crate::idx;
+use crate::simulation::{empty_matrix, ElementMatrix};
+use anyhow::{Context, Result};
+use nalgebra::Vector2;
+use png::{ColorType, Decoder, Transformations};
+use rand_xoshiro::SplitMix64;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Pixels with a lower alpha value become empty cells
+pub const TRANSPARENCY_THRESHOLD: u8 = 128;
+
+/// Maps pixel colors to elements. A pixel is mapped to the element with the nearest color,
+/// if the distance between the colors is at most `tolerance`
+#[derive(Clone, Debug)]
+pub struct Palette {
+    entries: Vec<(Color, ElementId)>,
+    /// Maximum euclidean distance between the red, green and blue channels of two matching colors
+    pub tolerance: f32,
+}
+
+impl Palette {
+    /// Creates an empty palette matching exact colors only
+    pub fn new() -> Self {
+        Palette {
+            entries: Vec::new(),
+            tolerance: 0.0,
+        }
+    }
+
+    /// Creates a palette of the colors of all elements in `registry`.
+    /// The tolerance allows for elements whose color varies between instances
+    pub fn from_registry(registry: &ElementRegistry) -> Self {
+        Palette {
+            entries: registry.iter().map(|x| (x.color, x.id)).collect(),
+            tolerance: 32.0,
+        }
+    }
+
+    /// Maps `color` to `element`, replacing a previous mapping of the same color
+    pub fn with(mut self, color: Color, element: ElementId) -> Self {
+        self.insert(color, element);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Maps `color` to `element`, replacing a previous mapping of the same color
+    pub fn insert(&mut self, color: Color, element: ElementId) {
+        self.entries.retain(|(x, _)| !same_rgb(*x, color));
+        self.entries.push((color, element));
+    }
+
+    /// Returns the element with the color nearest to `color` within the tolerance
+    pub fn nearest(&self, color: Color) -> Option<ElementId> {
+        self.entries
+            .iter()
+            .map(|&(x, id)| (distance_squared(x, color), id))
+            .filter(|&(distance, _)| distance as f32 <= self.tolerance * self.tolerance)
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, id)| id)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new()
+    }
+}
+
+fn same_rgb(a: Color, b: Color) -> bool {
+    distance_squared(a, b) == 0
+}
+
+fn distance_squared(a: Color, b: Color) -> u32 {
+    [(a.red, b.red), (a.green, b.green), (a.blue, b.blue)]
+        .into_iter()
+        .map(|(a, b)| (a as i32 - b as i32).pow(2) as u32)
+        .sum()
+}
+
+/// A color in an imported image which no palette entry matched
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnmappedColor {
+    pub color: Color,
+    /// Amount of pixels with this color
+    pub count: usize,
+    /// Position of the first pixel with this color
+    pub first: Vector2<usize>,
+}
+
+/// Result of importing an image
+pub struct ImportedScene {
+    pub matrix: ElementMatrix,
+    /// Colors which became empty cells because they didn't match any element, most frequent first
+    pub unmapped: Vec<UnmappedColor>,
+}
+
+/// Builds an element matrix from a PNG image, with one cell per pixel.
+/// Transparent pixels become empty cells, see [`TRANSPARENCY_THRESHOLD`]
+///
+/// # Arguments
+///
+/// * `reader` - The PNG image
+/// * `palette` - Maps pixel colors to elements
+/// * `registry` - Registry the elements of the palette are created with
+/// * `rng` - Source of the color variation of the created elements
+pub fn import_png(
+    reader: impl Read,
+    palette: &Palette,
+    registry: &ElementRegistry,
+    rng: &mut SplitMix64,
+) -> Result<ImportedScene> {
+    let mut decoder = Decoder::new(reader);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().context("Failed to read PNG header")?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .context("Failed to decode PNG")?;
+    let pixels = &buffer[..info.buffer_size()];
+    let channels = info.color_type.samples();
+    let width = info.width as usize;
+    let height = info.height as usize;
+
+    let mut matrix = empty_matrix(width, height);
+    let mut unmapped: HashMap<(u8, u8, u8), UnmappedColor> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            let offset = (y * width + x) * channels;
+            let color = pixel_color(info.color_type, &pixels[offset..offset + channels]);
+            if color.alpha < TRANSPARENCY_THRESHOLD {
+                continue;
+            }
+
+            let pos = Vector2::new(x, y);
+            match palette
+                .nearest(color)
+                .and_then(|id| registry.create(id, rng))
+            {
+                Some(element) => matrix[idx!(pos)] = Some(element),
+                None => {
+                    unmapped
+                        .entry((color.red, color.green, color.blue))
+                        .or_insert(UnmappedColor {
+                            color: Color::new(color.red, color.green, color.blue),
+                            count: 0,
+                            first: pos,
+                        })
+                        .count += 1
+                }
+            }
+        }
+    }
+
+    let mut unmapped = unmapped.into_values().collect::<Vec<_>>();
+    unmapped.sort_by_key(|x| (usize::MAX - x.count, x.first.y, x.first.x));
+    Ok(ImportedScene { matrix, unmapped })
+}
+
+/// Reads the PNG file at `path` and builds an element matrix from it, see [`import_png`]
+pub fn import_png_file(
+    path: impl AsRef<Path>,
+    palette: &Palette,
+    registry: &ElementRegistry,
+    rng: &mut SplitMix64,
+) -> Result<ImportedScene> {
+    let path = path.as_ref();
+    let file =
+        File::open(path).with_context(|| format!("Couldnt open image {}", path.display()))?;
+    import_png(BufReader::new(file), palette, registry, rng)
+        .with_context(|| format!("Couldnt import image {}", path.display()))
+}
+
+fn pixel_color(color_type: ColorType, pixel: &[u8]) -> Color {
+    match (color_type, pixel) {
+        (ColorType::Grayscale, &[gray]) => Color::new(gray, gray, gray),
+        (ColorType::GrayscaleAlpha, &[gray, alpha]) => {
+            Color::new_with_alpha(gray, gray, gray, alpha)
+        }
+        (ColorType::Rgb, &[red, green, blue]) => Color::new(red, green, blue),
+        (ColorType::Rgba, &[red, green, blue, alpha]) => {
+            Color::new_with_alpha(red, green, blue, alpha)
+        }
+        // Indexed images are expanded to RGB or RGBA by the decoder
+        _ => unreachable!("Unexpected pixel format {color_type:?}"),
+    }
+}
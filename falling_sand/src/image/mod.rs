@@ -0,0 +1,3 @@
+pub mod animation;
+pub mod import;
+pub mod raster;
@@ -0,0 +1,116 @@
+use crate::color::Color;
+use crate::idx;
+use crate::simulation::ElementMatrix;
+use anyhow::{ensure, Context, Result};
+use nalgebra::Vector2;
+use png::{ColorType, Encoder};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Settings of [`rasterize`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterOptions {
+    /// Width and height of a cell in pixels
+    pub scale: u32,
+    /// Color of empty cells
+    pub background: Color,
+}
+
+impl Default for RasterOptions {
+    /// Matches the wgpu renderer, which clears the screen white
+    fn default() -> Self {
+        RasterOptions {
+            scale: 1,
+            background: Color::white(),
+        }
+    }
+}
+
+/// An image with 8 bit red, green, blue and alpha channels, stored row by row
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Creates an image filled with `color`
+    pub fn new(width: u32, height: u32, color: Color) -> Self {
+        let pixel = [color.red, color.green, color.blue, color.alpha];
+        RgbaImage {
+            width,
+            height,
+            pixels: pixel.repeat(width as usize * height as usize),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let [red, green, blue, alpha] = self.pixels[offset..offset + 4] else {
+            unreachable!()
+        };
+        Color::new_with_alpha(red, green, blue, alpha)
+    }
+
+    /// Fills the rectangle with the top left corner `x`, `y` and the given size
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        let pixel = [color.red, color.green, color.blue, color.alpha];
+        for row in y..y + height {
+            let start = (row as usize * self.width as usize + x as usize) * 4;
+            for chunk in self.pixels[start..start + width as usize * 4].chunks_exact_mut(4) {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    /// Encodes the image as PNG
+    pub fn write_png(&self, writer: impl Write) -> Result<()> {
+        let mut encoder = Encoder::new(writer, self.width, self.height);
+        encoder.set_color(ColorType::Rgba);
+        let mut writer = encoder
+            .write_header()
+            .context("Failed to write PNG header")?;
+        writer
+            .write_image_data(&self.pixels)
+            .context("Failed to write PNG data")?;
+        writer.finish().context("Failed to finish PNG")
+    }
+
+    /// Writes the image as PNG file to `path`
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("Couldnt create {}", path.display()))?;
+        self.write_png(BufWriter::new(file))
+            .with_context(|| format!("Couldnt write image {}", path.display()))
+    }
+}
+
+/// Draws every cell of `matrix` as a square of `options.scale` pixels,
+/// using the same colors and layout as the wgpu renderer.
+/// Fails if the scale is zero or the image would be too large
+pub fn rasterize(matrix: &ElementMatrix, options: &RasterOptions) -> Result<RgbaImage> {
+    ensure!(options.scale > 0, "Scale has to be at least 1");
+    let scale = options.scale;
+    let size = |cells: usize| {
+        u32::try_from(cells)
+            .ok()
+            .and_then(|x| x.checked_mul(scale))
+            .context("Image is too large")
+    };
+    let width = size(matrix.ncols())?;
+    let height = size(matrix.nrows())?;
+
+    let mut image = RgbaImage::new(width, height, options.background);
+    for y in 0..matrix.nrows() {
+        for x in 0..matrix.ncols() {
+            if let Some(element) = &matrix[idx!(Vector2::new(x, y))] {
+                let color = element.properties.color();
+                image.fill(x as u32 * scale, y as u32 * scale, scale, scale, color);
+            }
+        }
+    }
+    Ok(image)
+}
@@ -1,8 +1,24 @@
 use nalgebra::Vector2;
 
+// Lets the ElementProperties derive refer to this crate as `::falling_sand` from within it
+extern crate self as falling_sand;
+
+// Used by code generated by the ElementProperties derive
+#[doc(hidden)]
+pub use anyhow;
+
+pub mod ascii;
 pub mod color;
 pub mod element_api;
 pub mod elements;
+#[cfg(feature = "png")]
+pub mod image;
+pub mod line;
+pub mod render;
+#[cfg(feature = "serde")]
+pub mod save;
+#[cfg(feature = "scene")]
+pub mod scene;
 pub mod simulation;
 
 pub const UP: Vector2<isize> = Vector2::new(0, -1);
@@ -10,9 +26,23 @@ pub const DOWN: Vector2<isize> = Vector2::new(0, 1);
 pub const LEFT: Vector2<isize> = Vector2::new(-1, 0);
 pub const RIGHT: Vector2<isize> = Vector2::new(1, 0);
 
+/// All eight relative positions surrounding a cell
+pub const NEIGHBOURS: [Vector2<isize>; 8] = [
+    Vector2::new(-1, -1),
+    Vector2::new(0, -1),
+    Vector2::new(1, -1),
+    Vector2::new(-1, 0),
+    Vector2::new(1, 0),
+    Vector2::new(-1, 1),
+    Vector2::new(0, 1),
+    Vector2::new(1, 1),
+];
+
+/// Converts a position `(x, y)` into a matrix index `(row, column)`.
+/// Rows are the y axis and columns the x axis, so a matrix has `height` rows and `width` columns
 #[macro_export]
 macro_rules! idx {
     ($index:expr) => {
-        ($index[0], $index[1])
+        ($index[1], $index[0])
     };
 }
@@ -0,0 +1,48 @@
+use nalgebra::Vector2;
+
+/// Iterator over the cells of a line between two positions using Bresenham's algorithm.
+///
+/// Yields every position after `from` up to and including `to`.
+/// Consecutive positions are always neighbours (including diagonals).
+#[derive(Clone, Debug)]
+pub struct Line {
+    current: Vector2<isize>,
+    to: Vector2<isize>,
+    delta: Vector2<isize>,
+    step: Vector2<isize>,
+    error: isize,
+}
+
+impl Line {
+    pub fn new(from: Vector2<isize>, to: Vector2<isize>) -> Self {
+        let delta = Vector2::new((to.x - from.x).abs(), -(to.y - from.y).abs());
+        Line {
+            current: from,
+            to,
+            delta,
+            step: Vector2::new((to.x - from.x).signum(), (to.y - from.y).signum()),
+            error: delta.x + delta.y,
+        }
+    }
+}
+
+impl Iterator for Line {
+    type Item = Vector2<isize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current == self.to {
+            return None;
+        }
+
+        let doubled_error = 2 * self.error;
+        if doubled_error >= self.delta.y {
+            self.error += self.delta.y;
+            self.current.x += self.step.x;
+        }
+        if doubled_error <= self.delta.x {
+            self.error += self.delta.x;
+            self.current.y += self.step.y;
+        }
+        Some(self.current)
+    }
+}
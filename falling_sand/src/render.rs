@@ -0,0 +1,207 @@
+//! Parts shared by all front ends: the [`InputEvent`]s they produce, the [`Session`] which
+//! applies those events to a simulation, and the [`Renderer`] trait with [`run`] for front ends
+//! which can wait for input themselves
+use crate::elements::registry::{ElementId, ElementRegistry, RegisteredElement};
+use crate::idx;
+use crate::simulation::{ElementMatrix, Simulation};
+use anyhow::Result;
+use nalgebra::Vector2;
+use std::collections::HashMap;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Time between two ticks of the simulation
+pub const TICK_INTERVAL: Duration = Duration::from_millis(10);
+/// Largest width of the painted square in cells
+pub const MAX_BRUSH: usize = 32;
+
+/// Input of the user, translated from the events of a front end
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    /// Places the selected element into the square of the brush around a cell,
+    /// or clears it while the eraser is selected
+    Paint(Vector2<usize>),
+    GrowBrush,
+    ShrinkBrush,
+    SelectNext,
+    SelectPrevious,
+    /// Selects an element of the palette, ignored for other elements
+    Select(ElementId),
+    /// Selects the eraser, which clears painted cells
+    SelectEraser,
+    TogglePause,
+    /// Asks for a new frame without changing anything, for example after the front end was resized
+    Redraw,
+    Quit,
+}
+
+/// Everything a front end shows
+pub struct Frame<'a> {
+    pub matrix: &'a ElementMatrix,
+    /// The element which is painted, `None` for the eraser
+    pub selected: Option<&'a RegisteredElement>,
+    /// Width of the painted square in cells
+    pub brush: usize,
+    pub paused: bool,
+    pub tick: u64,
+}
+
+/// A front end which draws frames and reports input when asked, driven by [`run`].
+/// Front ends with their own event loop, like a winit window, drive a [`Session`] directly
+pub trait Renderer {
+    /// Draws `frame`
+    fn render(&mut self, frame: &Frame) -> Result<()>;
+
+    /// Waits at most `timeout` for input and returns all events which arrived
+    fn poll_events(&mut self, timeout: Duration) -> Result<Vec<InputEvent>>;
+}
+
+/// Returns the drawable elements grouped by category
+pub fn palette(registry: &ElementRegistry) -> Vec<ElementId> {
+    registry
+        .by_category()
+        .into_iter()
+        .flat_map(|(_, elements)| elements)
+        .map(|x| x.id)
+        .collect()
+}
+
+/// Returns how many cells of `matrix` each element fills, in palette order and without empty elements
+pub fn element_counts(
+    matrix: &ElementMatrix,
+    registry: &ElementRegistry,
+) -> Vec<(ElementId, usize)> {
+    let mut counts = HashMap::new();
+    for element in matrix.iter().flatten() {
+        if let Some(id) = registry.id_of(element) {
+            *counts.entry(id).or_insert(0) += 1;
+        }
+    }
+    palette(registry)
+        .into_iter()
+        .filter_map(|id| counts.get(&id).map(|count| (id, *count)))
+        .collect()
+}
+
+/// A simulation together with the state of the user interface around it
+pub struct Session {
+    pub simulation: Simulation,
+    /// Elements which can be selected, in order
+    pub palette: Vec<ElementId>,
+    /// Index into [`Session::palette`], `None` for the eraser
+    pub selected: Option<usize>,
+    /// Width of the painted square in cells, at most [`MAX_BRUSH`]
+    pub brush: usize,
+    pub paused: bool,
+    /// Set once a [`InputEvent::Quit`] was handled
+    pub quit: bool,
+}
+
+impl Session {
+    /// Creates a session with the first element of the palette selected
+    pub fn new(simulation: Simulation) -> Self {
+        let palette = palette(&simulation.registry);
+        let selected = (!palette.is_empty()).then_some(0);
+        Session {
+            simulation,
+            palette,
+            selected,
+            brush: 1,
+            paused: false,
+            quit: false,
+        }
+    }
+
+    /// Returns the id of the element which is painted, `None` for the eraser
+    pub fn selected_element(&self) -> Option<ElementId> {
+        self.selected.and_then(|i| self.palette.get(i)).copied()
+    }
+
+    /// Applies `event` to the session
+    pub fn handle(&mut self, event: InputEvent) {
+        let last = self.palette.len().saturating_sub(1);
+        match event {
+            InputEvent::Paint(center) => {
+                let matrix = &self.simulation.matrix;
+                if center.x >= matrix.ncols() || center.y >= matrix.nrows() {
+                    return;
+                }
+                let start = center.map(|x| x.saturating_sub((self.brush - 1) / 2));
+                let end = Vector2::new(
+                    (start.x + self.brush).min(matrix.ncols()),
+                    (start.y + self.brush).min(matrix.nrows()),
+                );
+                for x in start.x..end.x {
+                    for y in start.y..end.y {
+                        let element = self.selected_element().and_then(|id| {
+                            self.simulation
+                                .registry
+                                .create(id, &mut self.simulation.rng)
+                        });
+                        self.simulation.matrix[idx!(Vector2::new(x, y))] = element;
+                    }
+                }
+            }
+            InputEvent::GrowBrush => self.brush = (self.brush + 1).min(MAX_BRUSH),
+            InputEvent::ShrinkBrush => self.brush = self.brush.saturating_sub(1).max(1),
+            InputEvent::SelectNext => {
+                self.selected = Some(self.selected.map_or(0, |i| (i + 1).min(last)));
+            }
+            InputEvent::SelectPrevious => {
+                self.selected = Some(self.selected.map_or(last, |i| i.saturating_sub(1)));
+            }
+            InputEvent::Select(id) => {
+                if let Some(i) = self.palette.iter().position(|x| *x == id) {
+                    self.selected = Some(i);
+                }
+            }
+            InputEvent::SelectEraser => self.selected = None,
+            InputEvent::TogglePause => self.paused = !self.paused,
+            InputEvent::Redraw => (),
+            InputEvent::Quit => self.quit = true,
+        }
+    }
+
+    /// Returns the current state of the session for a [`Renderer`]
+    pub fn frame(&self) -> Frame<'_> {
+        Frame {
+            matrix: &self.simulation.matrix,
+            selected: self
+                .selected_element()
+                .and_then(|id| self.simulation.registry.get(id)),
+            brush: self.brush,
+            paused: self.paused,
+            tick: self.simulation.tick_count(),
+        }
+    }
+}
+
+/// Ticks the simulation every [`TICK_INTERVAL`] unless paused and handles input in between,
+/// until the renderer reports [`InputEvent::Quit`].
+/// Redraws after every tick and every batch of input.
+/// Blocks until then, so it isn't available on the web where the browser runs the event loop
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run(renderer: &mut impl Renderer, session: &mut Session) -> Result<()> {
+    let mut last_tick = Instant::now();
+    renderer.render(&session.frame())?;
+    while !session.quit {
+        let timeout = TICK_INTERVAL.saturating_sub(last_tick.elapsed());
+        let events = renderer.poll_events(timeout)?;
+        let mut redraw = !events.is_empty();
+        for event in events {
+            session.handle(event);
+        }
+        if last_tick.elapsed() >= TICK_INTERVAL {
+            if !session.paused {
+                session.simulation.tick();
+                redraw = true;
+            }
+            last_tick = Instant::now();
+        }
+        if redraw && !session.quit {
+            renderer.render(&session.frame())?;
+        }
+    }
+    Ok(())
+}
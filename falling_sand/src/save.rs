@@ -0,0 +1,265 @@
+//! Binary save files of a [`Simulation`].
+//!
+//! A file starts with a header of the magic bytes, the format version and a checksum,
+//! followed by the bincode encoded body. The body contains a palette mapping the element ids
+//! used in the file to names, so files can be loaded even if the ids of elements changed.
+//! Cells are stored run-length encoded in the order of the matrix storage.
+//! The color of a cell is stored as fixed-size field instead of a key of its cell state.
+
+use crate::color::Color;
+use crate::elements::registry::{ElementId, ElementRegistry};
+use crate::elements::saved::SavedElement;
+use crate::elements::state::CellState;
+use crate::simulation::{ElementMatrix, Simulation, WorldParameters};
+use anyhow::{bail, ensure, Context, Result};
+use bincode::Options;
+use nalgebra::{Dyn, VecStorage, Vector2};
+use rand_xoshiro::SplitMix64;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 8] = b"FSWORLD\0";
+/// Version of files written by [`save_simulation`]
+pub const VERSION: u32 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 4 + 8;
+/// Worlds with more cells are rejected, so broken files can't exhaust memory when loading
+pub const MAX_CELLS: usize = 4096 * 4096;
+
+#[derive(Serialize, Deserialize)]
+struct WorldFile {
+    width: u32,
+    height: u32,
+    tick_count: u64,
+    rng: SplitMix64,
+    gravity: (i64, i64),
+    ambient_temperature: f32,
+    palette: Vec<PaletteEntry>,
+    runs: Vec<CellRun>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PaletteEntry {
+    id: ElementId,
+    name: String,
+}
+
+/// `length` consecutive cells which are equal
+#[derive(Serialize, Deserialize)]
+struct CellRun {
+    length: u32,
+    cell: Option<SavedCell>,
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+struct SavedCell {
+    /// Index into the palette
+    palette: u16,
+    color: Color,
+    /// Cell state without the color
+    state: CellState,
+}
+
+fn bincode() -> impl Options {
+    bincode::DefaultOptions::new().reject_trailing_bytes()
+}
+
+/// FNV-1a, only used to detect corrupted files
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Writes `simulation` in the save file format. Fails if a cell contains an element
+/// which isn't registered in the registry of the simulation
+pub fn save_simulation(simulation: &Simulation, mut writer: impl Write) -> Result<()> {
+    let (width, height) = (simulation.matrix.ncols(), simulation.matrix.nrows());
+    ensure!(
+        width * height <= MAX_CELLS,
+        "Can't save world of {width}x{height} cells, it has more than {MAX_CELLS} cells"
+    );
+
+    let mut palette = Vec::new();
+    let mut palette_indices = HashMap::new();
+    let mut runs: Vec<CellRun> = Vec::new();
+    for cell in simulation.matrix.iter() {
+        let cell = match cell {
+            None => None,
+            Some(element) => {
+                let id = simulation.registry.id_of(element).with_context(|| {
+                    format!(
+                        "Can't save element {:?}, it isn't registered",
+                        element.properties.name()
+                    )
+                })?;
+                let index = *palette_indices.entry(id).or_insert_with(|| {
+                    palette.push(PaletteEntry {
+                        id,
+                        name: element.properties.name().to_string(),
+                    });
+                    palette.len() - 1
+                });
+                let mut state = element.properties.state();
+                state.remove("color");
+                Some(SavedCell {
+                    palette: index as u16,
+                    color: element.properties.color(),
+                    state,
+                })
+            }
+        };
+        match runs.last_mut() {
+            Some(run) if run.cell == cell && run.length < u32::MAX => run.length += 1,
+            _ => runs.push(CellRun { length: 1, cell }),
+        }
+    }
+
+    let file = WorldFile {
+        width: width.try_into()?,
+        height: height.try_into()?,
+        tick_count: simulation.tick_count,
+        rng: simulation.rng.clone(),
+        gravity: (
+            simulation.world.gravity.x as i64,
+            simulation.world.gravity.y as i64,
+        ),
+        ambient_temperature: simulation.world.ambient_temperature,
+        palette,
+        runs,
+    };
+    let body = bincode()
+        .serialize(&file)
+        .context("Failed to encode world")?;
+
+    let mut checked = VERSION.to_le_bytes().to_vec();
+    checked.extend_from_slice(&body);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&checksum(&checked).to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush().context("Failed to write world")
+}
+
+/// Reads a simulation written by [`save_simulation`].
+/// Elements are resolved by name in `registry` first and by id if the name is unknown
+pub fn load_simulation(
+    mut reader: impl Read,
+    registry: Arc<ElementRegistry>,
+) -> Result<Simulation> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .context("Failed to read world")?;
+    ensure!(
+        bytes.len() >= HEADER_LEN,
+        "World file is truncated, it is only {} bytes long",
+        bytes.len()
+    );
+    let (magic, rest) = bytes.split_at(MAGIC.len());
+    ensure!(
+        magic == MAGIC,
+        "Not a world file, the magic bytes are wrong"
+    );
+    let (version, rest) = rest.split_at(4);
+    let (expected_checksum, body) = rest.split_at(8);
+    let version = u32::from_le_bytes(version.try_into()?);
+    let expected_checksum = u64::from_le_bytes(expected_checksum.try_into()?);
+
+    ensure!(
+        version <= VERSION,
+        "World file version {version} is newer than the supported version {VERSION}"
+    );
+
+    let mut checked = version.to_le_bytes().to_vec();
+    checked.extend_from_slice(body);
+    ensure!(
+        checksum(&checked) == expected_checksum,
+        "World file is corrupted or truncated, the checksum doesn't match"
+    );
+
+    let file = decode(version, body)?;
+    restore(file, registry)
+}
+
+/// Decodes the body of a file written with `version`.
+/// When the format changes, the previous layout is kept as its own struct,
+/// decoded here and migrated to the current [`WorldFile`]
+fn decode(version: u32, body: &[u8]) -> Result<WorldFile> {
+    match version {
+        1 => bincode()
+            .deserialize(body)
+            .context("World file is corrupted, failed to decode version 1"),
+        version => bail!("Unknown world file version {version}"),
+    }
+}
+
+fn restore(file: WorldFile, registry: Arc<ElementRegistry>) -> Result<Simulation> {
+    let width = file.width as usize;
+    let height = file.height as usize;
+    let size = width
+        .checked_mul(height)
+        .filter(|&x| x <= MAX_CELLS)
+        .with_context(|| format!("World of {width}x{height} cells is too large"))?;
+    let total = file.runs.iter().map(|x| x.length as u64).sum::<u64>();
+    ensure!(
+        total == size as u64,
+        "World file is corrupted, it contains {total} cells instead of {width}x{height}"
+    );
+
+    let mut cells = Vec::with_capacity(size);
+    for run in &file.runs {
+        let element = match &run.cell {
+            None => None,
+            Some(cell) => {
+                let entry = file.palette.get(cell.palette as usize).with_context(|| {
+                    format!(
+                        "World file is corrupted, palette index {} is out of range",
+                        cell.palette
+                    )
+                })?;
+                let mut state = cell.state.clone();
+                state.insert("color", &cell.color);
+                let saved = SavedElement {
+                    id: Some(entry.id),
+                    name: entry.name.clone(),
+                    state,
+                };
+                Some(saved.restore(&registry)?)
+            }
+        };
+        cells.extend(std::iter::repeat_n(element, run.length as usize));
+    }
+
+    let mut simulation = Simulation::new_with_rand(0, 0, file.rng);
+    simulation.matrix = ElementMatrix::from_data(VecStorage::new(Dyn(height), Dyn(width), cells));
+    simulation.registry = registry;
+    simulation.world = WorldParameters {
+        gravity: Vector2::new(file.gravity.0 as isize, file.gravity.1 as isize),
+        ambient_temperature: file.ambient_temperature,
+    };
+    simulation.tick_count = file.tick_count;
+    Ok(simulation)
+}
+
+impl Simulation {
+    /// Saves the simulation to `path`, see [`save_simulation`]
+    pub fn save_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        save_simulation(self, &mut bytes)?;
+        fs::write(path, bytes).with_context(|| format!("Couldnt write world {}", path.display()))
+    }
+
+    /// Loads a simulation saved with [`Simulation::save_file`]
+    pub fn load_file(path: impl AsRef<Path>, registry: Arc<ElementRegistry>) -> Result<Simulation> {
+        let path = path.as_ref();
+        let file = fs::File::open(path)
+            .with_context(|| format!("Couldnt open world {}", path.display()))?;
+        load_simulation(file, registry)
+            .with_context(|| format!("Couldnt load world {}", path.display()))
+    }
+}
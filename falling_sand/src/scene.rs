@@ -0,0 +1,143 @@
+//! Compact URL-safe strings of a scene, meant to be shared as links.
+//!
+//! The cells are run-length encoded as pairs of element id and run length, where id 0 is empty.
+//! All numbers are LEB128 varints. The result is deflate compressed and encoded as base64url
+//! without padding. The first byte is the version of the format and isn't compressed.
+//! Only the kind of each element is stored, cell state like varied colors is lost.
+
+use crate::elements::registry::{ElementId, ElementRegistry};
+use crate::simulation::ElementMatrix;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use nalgebra::{Dyn, VecStorage};
+use rand_xoshiro::SplitMix64;
+
+/// Version of strings written by [`encode_scene`]
+pub const VERSION: u8 = 1;
+/// Scenes with more cells are rejected when decoding, so links can't exhaust memory
+pub const MAX_CELLS: usize = 4096 * 4096;
+/// Longest varint of an element id, 16 bits at 7 bits per byte
+const MAX_ID_BYTES: usize = 3;
+/// Longest varint of a 64 bit number, which bounds the width and height
+const MAX_VARINT_BYTES: usize = 10;
+/// Upper bound of the decompressed payload of a valid scene.
+/// The length of a run of `n` cells never takes more than `n` bytes, so every run is at most
+/// `MAX_ID_BYTES + 1` bytes per cell. A scene of single cell runs with large ids is the worst case
+const MAX_PAYLOAD: usize = 2 * MAX_VARINT_BYTES + (MAX_ID_BYTES + 1) * MAX_CELLS;
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = bytes.next().context("Scene is truncated")?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Scene is corrupted, a number is too long")
+}
+
+/// Encodes the cells of `matrix` as URL-safe string.
+/// Fails if a cell contains an element which isn't registered in `registry`
+pub fn encode_scene(matrix: &ElementMatrix, registry: &ElementRegistry) -> Result<String> {
+    let mut payload = Vec::new();
+    write_varint(&mut payload, matrix.ncols() as u64);
+    write_varint(&mut payload, matrix.nrows() as u64);
+
+    let mut runs: Vec<(ElementId, u64)> = Vec::new();
+    for cell in matrix.iter() {
+        let id = match cell {
+            None => ElementId::EMPTY,
+            Some(element) => registry.id_of(element).with_context(|| {
+                format!(
+                    "Can't encode element {:?}, it isn't registered",
+                    element.properties.name()
+                )
+            })?,
+        };
+        match runs.last_mut() {
+            Some((last, length)) if *last == id => *length += 1,
+            _ => runs.push((id, 1)),
+        }
+    }
+    for (id, length) in runs {
+        write_varint(&mut payload, id.0 as u64);
+        write_varint(&mut payload, length);
+    }
+
+    let mut bytes = vec![VERSION];
+    bytes.extend(compress_to_vec(&payload, 9));
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Decodes a string written by [`encode_scene`], creating the elements through `registry`.
+/// The color variation of the elements is drawn from `rng`
+pub fn decode_scene(
+    scene: &str,
+    registry: &ElementRegistry,
+    rng: &mut SplitMix64,
+) -> Result<ElementMatrix> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(scene.trim())
+        .context("Scene isn't valid base64url")?;
+    let (&version, compressed) = bytes.split_first().context("Scene is empty")?;
+    ensure!(
+        version == VERSION,
+        "Unsupported scene version {version}, expected {VERSION}"
+    );
+    let payload = decompress_to_vec_with_limit(compressed, MAX_PAYLOAD)
+        .map_err(|err| anyhow!("Scene is corrupted, failed to decompress: {err}"))?;
+
+    let mut bytes = payload.into_iter();
+    let width = read_varint(&mut bytes)? as usize;
+    let height = read_varint(&mut bytes)? as usize;
+    let size = width
+        .checked_mul(height)
+        .filter(|&x| x <= MAX_CELLS)
+        .with_context(|| format!("Scene of {width}x{height} cells is too large"))?;
+
+    let mut cells = Vec::with_capacity(size);
+    while cells.len() < size {
+        let id = ElementId(
+            u16::try_from(read_varint(&mut bytes)?).context("Scene is corrupted, invalid id")?,
+        );
+        let length = read_varint(&mut bytes)? as usize;
+        ensure!(
+            length <= size - cells.len(),
+            "Scene is corrupted, it contains more than {width}x{height} cells"
+        );
+        if id == ElementId::EMPTY {
+            cells.resize_with(cells.len() + length, || None);
+        } else {
+            let element = registry
+                .get(id)
+                .with_context(|| format!("Scene contains unknown element {id}"))?;
+            cells.extend((0..length).map(|_| Some(element.create(rng))));
+        }
+    }
+    ensure!(
+        bytes.next().is_none(),
+        "Scene is corrupted, it contains trailing data"
+    );
+
+    Ok(ElementMatrix::from_data(VecStorage::new(
+        Dyn(height),
+        Dyn(width),
+        cells,
+    )))
+}
@@ -1,27 +1,92 @@
+pub mod channel;
+
 use crate::element_api::ElementApi;
+use crate::elements::behaviour::Behaviour;
 use crate::elements::element::Element;
-use crate::idx;
+use crate::elements::registry::ElementRegistry;
+use crate::simulation::channel::{fingerprints, TickActivity};
+use crate::{idx, DOWN};
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::anyhow;
+use anyhow::Result;
 use nalgebra::{DMatrix, Dim, Dyn, VecStorage, Vector2};
 use rand_core::SeedableRng;
 use rand_xoshiro::SplitMix64;
+use std::ops::ControlFlow;
+#[cfg(not(target_arch = "wasm32"))]
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
 
 pub type ElementMatrix = DMatrix<Option<Element>>;
 
+/// Creates a matrix of empty cells
+pub fn empty_matrix(width: usize, height: usize) -> ElementMatrix {
+    let data = VecStorage::new(
+        Dyn::from_usize(height),
+        Dyn::from_usize(width),
+        vec![None; width * height],
+    );
+    ElementMatrix::from_data(data)
+}
+
+/// Global settings of a simulation which every behaviour can read
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldParameters {
+    /// Relative direction elements fall towards
+    pub gravity: Vector2<isize>,
+    /// Temperature of the surroundings in degrees celsius
+    pub ambient_temperature: f32,
+}
+
+impl WorldParameters {
+    /// Returns the direction perpendicular to `gravity`, which is LEFT for the default gravity
+    pub fn side(&self) -> Vector2<isize> {
+        Vector2::new(-self.gravity.y, self.gravity.x)
+    }
+}
+
+impl Default for WorldParameters {
+    fn default() -> Self {
+        WorldParameters {
+            gravity: DOWN,
+            ambient_temperature: 20.0,
+        }
+    }
+}
+
+/// Read-only state of the simulation which is passed to every behaviour.
+/// New state is added here so the behaviour signature can stay the same
+#[derive(Clone, Copy)]
+pub struct TickContext<'a> {
+    /// Amount of ticks that passed before the current one
+    pub tick: u64,
+    pub world: &'a WorldParameters,
+    /// Elements available in the simulation
+    pub registry: &'a Arc<ElementRegistry>,
+}
+
 pub struct Simulation {
     pub matrix: ElementMatrix,
-    tick_visit: bool,
-    rng: SplitMix64,
+    pub world: WorldParameters,
+    /// Elements available in this simulation
+    pub registry: Arc<ElementRegistry>,
+    /// What happened during the last tick. Collecting it slows ticks down,
+    /// so it's only done while this is `Some`, see [`channel`]
+    pub activity: Option<TickActivity>,
+    pub(crate) tick_count: u64,
+    pub(crate) tick_visit: bool,
+    /// Source of all randomness, seeded for reproducible runs
+    pub rng: SplitMix64,
 }
 
 impl Simulation {
     pub fn new_with_rand(width: usize, height: usize, rng: SplitMix64) -> Self {
-        let data = VecStorage::new(
-            Dyn::from_usize(height),
-            Dyn::from_usize(width),
-            vec![None; width * height],
-        );
         Simulation {
-            matrix: ElementMatrix::from_data(data),
+            matrix: empty_matrix(width, height),
+            world: WorldParameters::default(),
+            registry: Arc::new(ElementRegistry::builtin()),
+            activity: None,
+            tick_count: 0,
             tick_visit: false,
             rng,
         }
@@ -31,7 +96,22 @@ impl Simulation {
         Self::new_with_rand(width, height, SplitMix64::from_entropy())
     }
 
+    /// Returns the amount of ticks that have passed
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
     pub fn tick(&mut self) {
+        let context = TickContext {
+            tick: self.tick_count,
+            world: &self.world,
+            registry: &self.registry,
+        };
+        let before = self.activity.as_mut().map(|activity| {
+            let (rows, cols) = self.matrix.shape();
+            activity.velocity = DMatrix::from_element(rows, cols, Vector2::zeros());
+            fingerprints(&self.matrix, &self.registry)
+        });
         for i in 0..self.matrix.ncols() {
             for j in (0..self.matrix.nrows()).rev() {
                 let pos = Vector2::new(i, j);
@@ -43,12 +123,44 @@ impl Simulation {
                             continue;
                         }
 
-                        (element.behaviour)(ElementApi::new(&mut self.matrix, &mut self.rng, pos));
+                        let behaviour = Arc::clone(&element.behaviour);
+                        let mut api =
+                            ElementApi::new(&mut self.matrix, &mut self.rng, context, pos);
+                        api.flip_visited();
+                        if let Err(err) = update_cell(behaviour.as_ref(), &mut api) {
+                            log::warn!("Skipping cell {pos:?}: {err:#}");
+                        }
+                        if let Some(activity) = &mut self.activity {
+                            let offset = api.position.cast::<f32>() - pos.cast::<f32>();
+                            if let Some(velocity) = activity.velocity.get_mut(idx!(api.position)) {
+                                *velocity = offset;
+                            }
+                        }
                     }
                 }
             }
         }
 
+        if let (Some(activity), Some(before)) = (&mut self.activity, before) {
+            activity.changed =
+                fingerprints(&self.matrix, &self.registry).zip_map(&before, |x, y| x != y);
+        }
+
         self.tick_visit = !self.tick_visit;
+        self.tick_count += 1;
     }
 }
+
+/// Runs `behaviour` for the cell of `api`. Behaviours report failures as errors,
+/// catching panics is only an extra on targets that unwind
+#[cfg(not(target_arch = "wasm32"))]
+fn update_cell(behaviour: &dyn Behaviour, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+    panic::catch_unwind(AssertUnwindSafe(|| behaviour.update(api)))
+        .unwrap_or_else(|_| Err(anyhow!("Behaviour panicked")))
+}
+
+/// Runs `behaviour` for the cell of `api`. Panics abort on wasm, so only errors are handled
+#[cfg(target_arch = "wasm32")]
+fn update_cell(behaviour: &dyn Behaviour, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+    behaviour.update(api)
+}
@@ -0,0 +1,222 @@
+//! Per-cell data of a simulation which can be visualized, like the temperature or velocity of every cell.
+
+use crate::color::Color;
+use crate::elements::element::Element;
+use crate::elements::registry::{ElementId, ElementRegistry};
+use crate::elements::state::StateValue;
+use crate::idx;
+use crate::simulation::{ElementMatrix, Simulation};
+use nalgebra::{DMatrix, Scalar, Vector2};
+
+/// Width and height of the areas activity is summarized in
+pub const CHUNK_SIZE: usize = 16;
+/// Key of the cell state the temperature channel reads
+pub const TEMPERATURE_KEY: &str = "temperature";
+/// Speed in cells per tick which is shown with full brightness
+const FULL_SPEED: f32 = 4.0;
+
+const TRANSPARENT: Color = Color {
+    red: 0,
+    green: 0,
+    blue: 0,
+    alpha: 0,
+};
+
+/// What happened to every cell during the last tick.
+/// Only collected while [`Simulation::activity`] is `Some`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TickActivity {
+    /// Offset every element moved during the last tick, at the position it ended up at.
+    /// Only the element whose behaviour ran is recorded, elements it displaced by swapping show zero
+    pub velocity: DMatrix<Vector2<f32>>,
+    /// Cells which hold a different element or color than before the last tick
+    pub changed: DMatrix<bool>,
+}
+
+impl Default for TickActivity {
+    fn default() -> Self {
+        TickActivity {
+            velocity: DMatrix::from_element(0, 0, Vector2::zeros()),
+            changed: DMatrix::from_element(0, 0, false),
+        }
+    }
+}
+
+impl TickActivity {
+    /// Returns for every area of [`CHUNK_SIZE`] cells whether anything changed or moved in it.
+    /// Areas without activity are idle. This is derived after the tick, the simulation still updates
+    /// every cell and doesn't skip idle areas. Indexed like the matrix, by `idx!` of the area position
+    pub fn active_chunks(&self) -> DMatrix<bool> {
+        let (rows, cols) = self.changed.shape();
+        let mut chunks =
+            DMatrix::from_element(rows.div_ceil(CHUNK_SIZE), cols.div_ceil(CHUNK_SIZE), false);
+        for col in 0..cols {
+            for row in 0..rows {
+                if self.changed[(row, col)] || self.velocity[(row, col)] != Vector2::zeros() {
+                    chunks[(row / CHUNK_SIZE, col / CHUNK_SIZE)] = true;
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// Identifies what a cell looks like, to find cells which changed during a tick
+pub(crate) type Fingerprint = Option<(Color, Option<ElementId>)>;
+
+pub(crate) fn fingerprints(
+    matrix: &ElementMatrix,
+    registry: &ElementRegistry,
+) -> DMatrix<Fingerprint> {
+    let (rows, cols) = matrix.shape();
+    DMatrix::from_iterator(
+        rows,
+        cols,
+        matrix.iter().map(|cell| {
+            cell.as_ref()
+                .map(|element| (element.properties.color(), registry.id_of(element)))
+        }),
+    )
+}
+
+/// Builtin channels, see [`Simulation::channel`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// Temperature in degrees celsius, read from the [`TEMPERATURE_KEY`] of the cell state.
+    /// Cells without one have the ambient temperature
+    Temperature,
+    /// Direction of the last movement as hue and its speed as brightness,
+    /// see [`TickActivity::velocity`] for which movements are recorded
+    Velocity,
+    /// Outlines of active areas in green and idle areas in grey, see [`TickActivity::active_chunks`]
+    Chunks,
+    /// Highlights cells which changed during the last tick
+    Changed,
+    /// A different color for every element id
+    ElementId,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 5] = [
+        Channel::Temperature,
+        Channel::Velocity,
+        Channel::Chunks,
+        Channel::Changed,
+        Channel::ElementId,
+    ];
+
+    /// Returns true if the channel is computed from [`Simulation::activity`]
+    pub fn needs_activity(self) -> bool {
+        matches!(self, Channel::Velocity | Channel::Chunks | Channel::Changed)
+    }
+}
+
+/// Values of a channel, one for every cell and indexed like the matrix
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChannelData {
+    /// Numbers which are best shown as heatmap, like temperatures
+    Scalar(DMatrix<f32>),
+    /// Colors to draw over the cells, transparent where there is nothing to show
+    Color(DMatrix<Color>),
+}
+
+impl Simulation {
+    /// Computes a value for every cell from its position and element
+    pub fn cell_channel<T: Scalar>(
+        &self,
+        mut value: impl FnMut(Vector2<usize>, Option<&Element>) -> T,
+    ) -> DMatrix<T> {
+        let (rows, cols) = self.matrix.shape();
+        DMatrix::from_fn(rows, cols, |row, col| {
+            let pos = Vector2::new(col, row);
+            value(pos, self.matrix[idx!(pos)].as_ref())
+        })
+    }
+
+    /// Computes one of the builtin channels. The channels based on activity are transparent
+    /// while [`Simulation::activity`] is `None` or hasn't been collected for the current matrix
+    pub fn channel(&self, channel: Channel) -> ChannelData {
+        let activity = self
+            .activity
+            .as_ref()
+            .filter(|x| x.changed.shape() == self.matrix.shape());
+        match channel {
+            Channel::Temperature => ChannelData::Scalar(self.cell_channel(|_, cell| {
+                cell.and_then(temperature)
+                    .unwrap_or(self.world.ambient_temperature)
+            })),
+            Channel::Velocity => ChannelData::Color(self.cell_channel(|pos, _| {
+                let Some(velocity) = activity.map(|x| x.velocity[idx!(pos)]) else {
+                    return TRANSPARENT;
+                };
+                if velocity == Vector2::zeros() {
+                    return TRANSPARENT;
+                }
+                let hue = velocity.y.atan2(velocity.x) / std::f32::consts::TAU;
+                let brightness = (velocity.norm() / FULL_SPEED).min(1.0);
+                with_alpha(hue_color(hue, brightness), 220)
+            })),
+            Channel::Chunks => {
+                let chunks = activity.map(TickActivity::active_chunks);
+                let (rows, cols) = self.matrix.shape();
+                ChannelData::Color(self.cell_channel(|pos, _| {
+                    let Some(chunks) = &chunks else {
+                        return TRANSPARENT;
+                    };
+                    let border = |x: usize, size: usize| {
+                        let offset = x % CHUNK_SIZE;
+                        offset == 0 || offset == CHUNK_SIZE - 1 || x == size - 1
+                    };
+                    if !border(pos.x, cols) && !border(pos.y, rows) {
+                        TRANSPARENT
+                    } else if chunks[idx!(pos / CHUNK_SIZE)] {
+                        Color::new(40, 200, 60)
+                    } else {
+                        Color::new_with_alpha(120, 120, 120, 160)
+                    }
+                }))
+            }
+            Channel::Changed => ChannelData::Color(self.cell_channel(|pos, _| {
+                match activity.map(|x| x.changed[idx!(pos)]) {
+                    Some(true) => Color::new_with_alpha(255, 230, 0, 200),
+                    _ => TRANSPARENT,
+                }
+            })),
+            Channel::ElementId => ChannelData::Color(self.cell_channel(|_, cell| {
+                match cell.and_then(|x| self.registry.id_of(x)) {
+                    // Golden ratio steps spread the hues of consecutive ids
+                    Some(id) => hue_color(id.0 as f32 * 0.618_034, 1.0),
+                    None => TRANSPARENT,
+                }
+            })),
+        }
+    }
+}
+
+fn temperature(element: &Element) -> Option<f32> {
+    match element.properties.state().get(TEMPERATURE_KEY)? {
+        StateValue::Float(x) => Some(*x as f32),
+        StateValue::Int(x) => Some(*x as f32),
+        _ => None,
+    }
+}
+
+fn with_alpha(color: Color, alpha: u8) -> Color {
+    Color { alpha, ..color }
+}
+
+/// Fully saturated color of `hue` in turns, where only the fractional part matters
+fn hue_color(hue: f32, brightness: f32) -> Color {
+    let h = hue.rem_euclid(1.0) * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let channel = |x: f32| (x * brightness * 255.0).round() as u8;
+    Color::new(channel(r), channel(g), channel(b))
+}
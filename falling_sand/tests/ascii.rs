@@ -0,0 +1,70 @@
+use falling_sand::ascii::{parse_ascii, Legend};
+use falling_sand::elements::registry::ElementRegistry;
+use falling_sand::simulation::Simulation;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+
+#[test]
+fn round_trip() {
+    let text = "\
+..S.
+.WW.
+S..#
+####
+";
+    let simulation = Simulation::from_ascii(text).unwrap();
+    assert_eq!(simulation.to_ascii(), text);
+}
+
+#[test]
+fn ignores_indentation() {
+    let simulation = Simulation::from_ascii(
+        "
+        .S
+        ##
+        ",
+    )
+    .unwrap();
+    assert_eq!(simulation.to_ascii(), ".S\n##\n");
+}
+
+#[test]
+fn sand_falls() {
+    let mut simulation = Simulation::from_ascii(
+        "
+        .S.
+        ...
+        ...
+        ",
+    )
+    .unwrap();
+    for _ in 0..3 {
+        simulation.tick();
+    }
+    assert_eq!(simulation.to_ascii(), "...\n...\n.S.\n");
+}
+
+#[test]
+fn custom_legend() {
+    let registry = ElementRegistry::builtin();
+    let legend = Legend::new('_').with('~', "Water").with('x', "Stone");
+    let mut simulation = Simulation::new(0, 0);
+    simulation.matrix = parse_ascii("~~\nxx", &legend, &registry, &mut simulation.rng).unwrap();
+    assert_eq!(simulation.to_ascii(), "WW\n##\n");
+    assert_eq!(simulation.to_ascii_with(&legend), "~~\nxx\n");
+    assert_eq!(
+        simulation.to_ascii_with(&Legend::new('_').with('x', "Stone")),
+        "??\nxx\n"
+    );
+}
+
+#[test]
+fn reports_errors() {
+    let error = |text| format!("{:#}", Simulation::from_ascii(text).err().unwrap());
+    assert!(error("..\n.").contains("Line 2"));
+    assert!(error("..\n.?").contains("column 2"));
+    let legend = Legend::default().with('G', "Glass");
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    assert!(parse_ascii("G", &legend, &registry, &mut rng).is_err());
+}
@@ -0,0 +1,126 @@
+use anyhow::{bail, Result};
+use falling_sand::element_api::ElementApi;
+use falling_sand::elements::behaviour::chain::BehaviourChain;
+use falling_sand::elements::behaviour::Behaviour;
+use falling_sand::elements::registry::ElementId;
+use falling_sand::idx;
+use falling_sand::simulation::{Simulation, TickContext};
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Removes its own element from the matrix and then accesses it
+struct Vanishing {
+    panicking: bool,
+}
+
+impl Behaviour for Vanishing {
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        let position = api.position;
+        api.matrix[idx!(position)] = None;
+        if self.panicking {
+            api.element();
+        }
+        api.try_element()?;
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+fn skips_failing_cell(panicking: bool) {
+    let mut simulation = Simulation::from_ascii(
+        "
+        S.S
+        ...
+        ",
+    )
+    .unwrap();
+    let element = simulation.matrix[(0, 0)].as_mut().unwrap();
+    element.behaviour = Arc::new(Vanishing { panicking });
+
+    simulation.tick();
+    assert_eq!(simulation.tick_count(), 1);
+    assert_eq!(simulation.to_ascii(), "...\n..S\n");
+    let sand = simulation.matrix[(1, 2)].as_ref().unwrap();
+    assert_eq!(simulation.registry.id_of(sand), Some(ElementId::SAND));
+}
+
+#[test]
+fn skips_cell_whose_behaviour_fails() {
+    skips_failing_cell(false);
+}
+
+#[test]
+fn skips_cell_whose_behaviour_panics() {
+    skips_failing_cell(true);
+}
+
+/// Counts its calls and returns `flow`, or fails if `flow` is `None`
+struct Counting {
+    calls: Arc<AtomicUsize>,
+    flow: Option<ControlFlow<()>>,
+}
+
+impl Behaviour for Counting {
+    fn update(&self, _api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        match self.flow {
+            Some(flow) => Ok(flow),
+            None => bail!("Counting failed"),
+        }
+    }
+}
+
+/// Runs a chain of a behaviour returning `first` and one continuing.
+/// Returns the result and how often each of them was called
+fn run_chain(first: Option<ControlFlow<()>>) -> (Result<ControlFlow<()>>, [usize; 2]) {
+    let calls = [Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0))];
+    let chain = BehaviourChain::new()
+        .then(Counting {
+            calls: Arc::clone(&calls[0]),
+            flow: first,
+        })
+        .then(Counting {
+            calls: Arc::clone(&calls[1]),
+            flow: Some(ControlFlow::Continue(())),
+        });
+
+    let mut simulation = Simulation::from_ascii("S").unwrap();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let context = TickContext {
+        tick: 0,
+        world: &simulation.world,
+        registry: &simulation.registry,
+    };
+    let mut api = ElementApi::new(
+        &mut simulation.matrix,
+        &mut rng,
+        context,
+        Vector2::new(0, 0),
+    );
+    let result = chain.update(&mut api);
+    (result, calls.map(|x| x.load(Ordering::Relaxed)))
+}
+
+#[test]
+fn chain_stops_at_break() {
+    let (result, calls) = run_chain(Some(ControlFlow::Break(())));
+    assert_eq!(result.unwrap(), ControlFlow::Break(()));
+    assert_eq!(calls, [1, 0]);
+}
+
+#[test]
+fn chain_runs_all_on_continue() {
+    let (result, calls) = run_chain(Some(ControlFlow::Continue(())));
+    assert_eq!(result.unwrap(), ControlFlow::Continue(()));
+    assert_eq!(calls, [1, 1]);
+}
+
+#[test]
+fn chain_stops_at_error() {
+    let (result, calls) = run_chain(None);
+    assert!(result.is_err());
+    assert_eq!(calls, [1, 0]);
+}
@@ -0,0 +1,69 @@
+use falling_sand::simulation::channel::{Channel, ChannelData, TickActivity};
+use falling_sand::simulation::Simulation;
+use nalgebra::Vector2;
+
+#[test]
+fn tracks_activity() {
+    let mut simulation = Simulation::from_ascii(
+        "
+        .S.
+        ...
+        ...
+        ...
+        ###
+        ",
+    )
+    .unwrap();
+    simulation.activity = Some(TickActivity::default());
+    simulation.tick();
+
+    let activity = simulation.activity.as_ref().unwrap();
+    let (row, _) = simulation
+        .to_ascii()
+        .lines()
+        .enumerate()
+        .find(|(_, line)| line.contains('S'))
+        .unwrap();
+    assert!(row > 0);
+    assert_eq!(activity.velocity[(row, 1)], Vector2::new(0.0, row as f32));
+    assert!(activity.changed[(0, 1)]);
+    assert!(activity.changed[(row, 1)]);
+    assert_eq!(activity.changed.iter().filter(|x| **x).count(), 2);
+}
+
+#[test]
+fn finds_idle_areas() {
+    let mut simulation = Simulation::new(40, 20);
+    simulation.matrix[(0, 35)] = simulation
+        .registry
+        .create_by_name("Sand", &mut simulation.rng);
+    simulation.activity = Some(TickActivity::default());
+    simulation.tick();
+
+    let chunks = simulation.activity.unwrap().active_chunks();
+    assert_eq!(chunks.shape(), (2, 3));
+    assert_eq!(chunks.iter().filter(|x| **x).count(), 1);
+    assert!(chunks[(0, 2)]);
+}
+
+#[test]
+fn temperature_defaults_to_ambient() {
+    let mut simulation = Simulation::from_ascii("S.\n##").unwrap();
+    simulation.world.ambient_temperature = 30.0;
+    let ChannelData::Scalar(temperatures) = simulation.channel(Channel::Temperature) else {
+        panic!("Temperature is a scalar channel");
+    };
+    assert!(temperatures.iter().all(|x| *x == 30.0));
+}
+
+#[test]
+fn activity_channels_need_tracking() {
+    let mut simulation = Simulation::from_ascii("S.\n..").unwrap();
+    simulation.tick();
+    for channel in Channel::ALL.into_iter().filter(|x| x.needs_activity()) {
+        let ChannelData::Color(colors) = simulation.channel(channel) else {
+            panic!("{channel:?} is a color channel");
+        };
+        assert!(colors.iter().all(|x| x.alpha == 0), "{channel:?}");
+    }
+}
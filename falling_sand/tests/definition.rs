@@ -0,0 +1,100 @@
+use falling_sand::elements::definition::load_definitions;
+use falling_sand::elements::registry::ElementRegistry;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::path::Path;
+
+/// Loads `source` into a builtin registry, which has to fail, and returns the error message
+fn definition_error(source: &str) -> String {
+    let mut registry = ElementRegistry::builtin();
+    let error = load_definitions(source, Path::new(""), &mut registry)
+        .err()
+        .unwrap();
+    assert_eq!(registry.len(), 3);
+    format!("{error:#}")
+}
+
+#[test]
+fn rejects_unknown_behaviour() {
+    let error = definition_error(
+        r#"[(name: "Gas", color: (200, 200, 200), swap_priority: 10, behaviour: [Float])]"#,
+    );
+    assert!(error.contains("Float"), "{error}");
+}
+
+#[test]
+fn rejects_unknown_field() {
+    let error = definition_error(
+        r#"[(name: "Salt", color: (240, 240, 235), swap_priority: 200, behaviour: [Powder], density: 2)]"#,
+    );
+    assert!(error.contains("density"), "{error}");
+}
+
+#[test]
+fn rejects_liquid_without_spread() {
+    let error = definition_error(
+        r#"[(name: "Oil", color: (60, 50, 20), swap_priority: 90, behaviour: [Liquid(spread: 0)])]"#,
+    );
+    assert!(error.contains("spread"), "{error}");
+}
+
+#[test]
+fn rejects_missing_script() {
+    let error = definition_error(
+        r#"[(name: "Acid", color: (120, 250, 80), swap_priority: 90, behaviour: [Script("missing.rhai")])]"#,
+    );
+    assert!(error.contains("missing.rhai"), "{error}");
+}
+
+#[test]
+fn rejects_empty_behaviour() {
+    let error =
+        definition_error(r#"[(name: "Void", color: (0, 0, 0), swap_priority: 0, behaviour: [])]"#);
+    assert!(error.contains("at least one behaviour"), "{error}");
+}
+
+#[test]
+fn color_variation_follows_seed() {
+    let colors = |seed| {
+        let mut registry = ElementRegistry::builtin();
+        load_definitions(
+            r#"[(name: "Salt", color: (240, 240, 235), color_variation: 10, swap_priority: 200, behaviour: [Powder])]"#,
+            Path::new(""),
+            &mut registry,
+        )
+        .unwrap();
+        let mut rng = SplitMix64::seed_from_u64(seed);
+        (0..20)
+            .map(|_| registry.create_by_name("Salt", &mut rng).unwrap())
+            .map(|salt| salt.properties.color())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(colors(1), colors(1));
+    assert_ne!(colors(1), colors(2));
+}
+
+#[test]
+fn color_variation_is_uniform() {
+    let mut registry = ElementRegistry::builtin();
+    load_definitions(
+        r#"[(name: "Ash", color: (100, 100, 100), color_variation: 2, swap_priority: 200, behaviour: [Powder])]"#,
+        Path::new(""),
+        &mut registry,
+    )
+    .unwrap();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let mut counts = [0; 5];
+    for _ in 0..5000 {
+        let color = registry
+            .create_by_name("Ash", &mut rng)
+            .unwrap()
+            .properties
+            .color();
+        assert!(color.red == color.green && color.green == color.blue);
+        counts[color.red as usize - 98] += 1;
+    }
+    assert!(
+        counts.iter().all(|&x| (850..1150).contains(&x)),
+        "{counts:?}"
+    );
+}
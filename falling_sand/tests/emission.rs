@@ -0,0 +1,47 @@
+use falling_sand::color::Color;
+use falling_sand::elements::behaviour::solid_behaviour::SolidBehaviour;
+use falling_sand::elements::definition::load_definitions;
+use falling_sand::elements::element::ElementProperties;
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::path::Path;
+
+#[derive(Clone, ElementProperties)]
+#[element(name = "Glowstone", color = (250, 230, 120), swap_priority = 212, behaviour = SolidBehaviour, emission = 0.75)]
+pub struct GlowstoneProperties {
+    color: Color,
+    visited: bool,
+}
+
+#[test]
+fn derived_emission() {
+    assert_eq!(new_glowstone().properties.emission(), 0.75);
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let sand = registry.create(ElementId::SAND, &mut rng).unwrap();
+    assert_eq!(sand.properties.emission(), 0.0);
+}
+
+#[test]
+fn defined_emission() {
+    let mut registry = ElementRegistry::builtin();
+    load_definitions(
+        r#"[(name: "Lava", color: (230, 80, 20), swap_priority: 150, behaviour: [Liquid(spread: 2)], emission: 0.9)]"#,
+        Path::new(""),
+        &mut registry,
+    )
+    .unwrap();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let lava = registry.create_by_name("Lava", &mut rng).unwrap();
+    assert_eq!(lava.properties.emission(), 0.9);
+
+    let error = load_definitions(
+        r#"[(name: "Sun", color: (255, 255, 200), swap_priority: 150, behaviour: [Solid], emission: 2.0)]"#,
+        Path::new(""),
+        &mut registry,
+    )
+    .err()
+    .unwrap();
+    assert!(format!("{error:#}").contains("Emission"));
+}
@@ -0,0 +1,260 @@
+#![cfg(feature = "png")]
+
+use falling_sand::color::Color;
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use falling_sand::image::animation::{record_animation, AnimationFormat, AnimationOptions};
+use falling_sand::image::import::{import_png, Palette};
+use falling_sand::image::raster::{rasterize, RasterOptions};
+use falling_sand::simulation::{ElementMatrix, Simulation};
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+
+/// Encodes `rows` of RGBA pixels as PNG
+fn encode(rows: &[[[u8; 4]; 4]; 4]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, 4, 4);
+    encoder.set_color(png::ColorType::Rgba);
+    let mut writer = encoder.write_header().unwrap();
+    writer
+        .write_image_data(rows.as_flattened().as_flattened())
+        .unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+fn names(matrix: &ElementMatrix) -> Vec<Vec<&str>> {
+    (0..4)
+        .map(|y| {
+            (0..4)
+                .map(|x| {
+                    matrix[(y, x)]
+                        .as_ref()
+                        .map_or(".", |element| element.properties.name())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+const CLEAR: [u8; 4] = [0, 0, 0, 0];
+const SAND: [u8; 4] = [239, 221, 111, 255];
+const NEAR_SAND: [u8; 4] = [230, 215, 120, 255];
+const WATER: [u8; 4] = [29, 162, 255, 255];
+const MAGENTA: [u8; 4] = [255, 0, 255, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+const STONE: [u8; 4] = [60, 60, 60, 255];
+
+#[test]
+fn maps_element_colors() {
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let image = encode(&[
+        [CLEAR, CLEAR, CLEAR, MAGENTA],
+        [SAND, CLEAR, WATER, CLEAR],
+        [NEAR_SAND, WATER, WATER, MAGENTA],
+        [STONE, STONE, STONE, STONE],
+    ]);
+
+    let scene = import_png(
+        image.as_slice(),
+        &Palette::from_registry(&registry),
+        &registry,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(
+        names(&scene.matrix),
+        [
+            [".", ".", ".", "."],
+            ["Sand", ".", "Water", "."],
+            ["Sand", "Water", "Water", "."],
+            ["Stone", "Stone", "Stone", "Stone"],
+        ]
+    );
+    assert_eq!(scene.unmapped.len(), 1);
+    assert_eq!(scene.unmapped[0].color, Color::new(255, 0, 255));
+    assert_eq!(scene.unmapped[0].count, 2);
+    assert_eq!(
+        (scene.unmapped[0].first.x, scene.unmapped[0].first.y),
+        (3, 0)
+    );
+}
+
+#[test]
+fn custom_palette_with_tolerance() {
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let image = encode(&[
+        [MAGENTA, [250, 10, 250, 255], CLEAR, CLEAR],
+        [SAND, NEAR_SAND, CLEAR, CLEAR],
+        [CLEAR; 4],
+        [BLACK; 4],
+    ]);
+    let palette = Palette::new()
+        .with(Color::new(255, 0, 255), ElementId::WATER)
+        .with(Color::new(239, 221, 111), ElementId::SAND);
+
+    let exact = import_png(image.as_slice(), &palette, &registry, &mut rng).unwrap();
+    assert_eq!(names(&exact.matrix)[0], ["Water", ".", ".", "."]);
+    assert_eq!(names(&exact.matrix)[1], ["Sand", ".", ".", "."]);
+    assert_eq!(exact.unmapped.len(), 3);
+    assert_eq!(exact.unmapped[0].color, Color::new(0, 0, 0));
+
+    let tolerant = import_png(
+        image.as_slice(),
+        &palette.with_tolerance(20.0),
+        &registry,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(names(&tolerant.matrix)[0], ["Water", "Water", ".", "."]);
+    assert_eq!(names(&tolerant.matrix)[1], ["Sand", "Sand", ".", "."]);
+    assert_eq!(tolerant.unmapped.len(), 1);
+}
+
+#[test]
+fn rejects_invalid_images() {
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let palette = Palette::from_registry(&registry);
+    assert!(import_png(&b"not a png"[..], &palette, &registry, &mut rng).is_err());
+}
+
+#[test]
+fn rasterized_scenes_import_unchanged() {
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let image = encode(&[
+        [CLEAR, SAND, CLEAR, WATER],
+        [SAND, CLEAR, WATER, CLEAR],
+        [NEAR_SAND, WATER, WATER, CLEAR],
+        [STONE, STONE, STONE, STONE],
+    ]);
+    let palette = Palette::from_registry(&registry);
+    let scene = import_png(image.as_slice(), &palette, &registry, &mut rng).unwrap();
+
+    let options = RasterOptions {
+        scale: 1,
+        background: Color::new_with_alpha(0, 0, 0, 0),
+    };
+    let mut png = Vec::new();
+    rasterize(&scene.matrix, &options)
+        .unwrap()
+        .write_png(&mut png)
+        .unwrap();
+    let reimported = import_png(
+        png.as_slice(),
+        &Palette::from_registry(&registry),
+        &registry,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(names(&reimported.matrix), names(&scene.matrix));
+    assert!(reimported.unmapped.is_empty());
+}
+
+#[test]
+fn rasterize_scales_cells() {
+    let registry = ElementRegistry::builtin();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let image = encode(&[
+        [CLEAR; 4],
+        [CLEAR, SAND, CLEAR, CLEAR],
+        [CLEAR; 4],
+        [CLEAR; 4],
+    ]);
+    let palette = Palette::from_registry(&registry);
+    let scene = import_png(image.as_slice(), &palette, &registry, &mut rng).unwrap();
+
+    let options = RasterOptions {
+        scale: 3,
+        background: Color::black(),
+    };
+    let raster = rasterize(&scene.matrix, &options).unwrap();
+    assert_eq!((raster.width, raster.height), (12, 12));
+    let sand = Color::new(239, 221, 111);
+    for y in 0..12 {
+        for x in 0..12 {
+            let inside = (3..6).contains(&x) && (3..6).contains(&y);
+            let expected = if inside { sand } else { Color::black() };
+            assert_eq!(raster.pixel(x, y), expected, "pixel {x} {y}");
+        }
+    }
+
+    let zero = RasterOptions {
+        scale: 0,
+        ..RasterOptions::default()
+    };
+    assert!(rasterize(&scene.matrix, &zero).is_err());
+}
+
+fn falling_sand() -> Simulation {
+    let mut simulation = Simulation::new_with_rand(4, 4, SplitMix64::seed_from_u64(3));
+    simulation.matrix[(0, 1)] = simulation
+        .registry
+        .create(ElementId::SAND, &mut simulation.rng);
+    simulation
+}
+
+#[test]
+fn records_apng() {
+    let mut simulation = falling_sand();
+    let options = AnimationOptions {
+        frame_skip: 2,
+        raster: RasterOptions {
+            scale: 2,
+            background: Color::black(),
+        },
+        ..AnimationOptions::default()
+    };
+    let mut bytes = Vec::new();
+    record_animation(&mut simulation, 5, &options, &mut bytes).unwrap();
+    assert_eq!(simulation.tick_count(), 5);
+
+    let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (8, 8));
+    assert_eq!(info.color_type, png::ColorType::Indexed);
+    assert_eq!(info.animation_control.unwrap().num_frames, 3);
+    let delay = info.frame_control.unwrap();
+    assert_eq!((delay.delay_num, delay.delay_den), (20, 1000));
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let mut frames = 0;
+    while reader.next_frame(&mut buffer).is_ok() {
+        frames += 1;
+    }
+    assert_eq!(frames, 3);
+}
+
+#[cfg(feature = "gif")]
+#[test]
+fn records_gif() {
+    let mut simulation = falling_sand();
+    let options = AnimationOptions {
+        format: AnimationFormat::Gif,
+        ..AnimationOptions::default()
+    };
+    let mut bytes = Vec::new();
+    record_animation(&mut simulation, 3, &options, &mut bytes).unwrap();
+
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(bytes.as_slice())
+        .unwrap();
+    let mut frames = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!(frame.delay, 1);
+        frames += 1;
+    }
+    assert_eq!(frames, 4);
+}
+
+#[test]
+fn rejects_zero_frame_skip() {
+    let options = AnimationOptions {
+        format: AnimationFormat::Apng,
+        frame_skip: 0,
+        ..AnimationOptions::default()
+    };
+    assert!(record_animation(&mut falling_sand(), 3, &options, Vec::new()).is_err());
+}
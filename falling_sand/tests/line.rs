@@ -0,0 +1,153 @@
+use falling_sand::element_api::{ElementApi, Movement, Obstacle};
+use falling_sand::line::Line;
+use falling_sand::simulation::{Simulation, TickContext};
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+
+/// Runs `f` with an api for the element at `position` of the grid `ascii`.
+/// Returns the grid afterwards
+fn with_api(ascii: &str, position: Vector2<usize>, f: impl FnOnce(&mut ElementApi)) -> String {
+    let mut simulation = Simulation::from_ascii(ascii).unwrap();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let context = TickContext {
+        tick: 0,
+        world: &simulation.world,
+        registry: &simulation.registry,
+    };
+    let mut api = ElementApi::new(&mut simulation.matrix, &mut rng, context, position);
+    f(&mut api);
+    simulation.to_ascii()
+}
+
+#[test]
+fn line_ends_at_target() {
+    let from = Vector2::new(2, -1);
+    let to = Vector2::new(-3, 1);
+    let points = Line::new(from, to).collect::<Vec<_>>();
+    assert_eq!(points.len(), 5);
+    assert!(!points.contains(&from));
+    assert_eq!(points.last(), Some(&to));
+}
+
+#[test]
+fn zero_length_line_is_empty() {
+    let point = Vector2::new(3, 4);
+    assert_eq!(Line::new(point, point).next(), None);
+}
+
+#[test]
+fn line_stays_close_in_every_octant() {
+    for to in [
+        Vector2::new(7, 3),
+        Vector2::new(3, 7),
+        Vector2::new(-3, 7),
+        Vector2::new(-7, 3),
+        Vector2::new(-7, -3),
+        Vector2::new(-3, -7),
+        Vector2::new(3, -7),
+        Vector2::new(7, -3),
+        Vector2::new(5, 5),
+        Vector2::new(0, -4),
+    ] {
+        let points = Line::new(Vector2::zeros(), to).collect::<Vec<_>>();
+        assert_eq!(points.len() as isize, to.x.abs().max(to.y.abs()), "{to}");
+        assert_eq!(points.last(), Some(&to));
+
+        let mut previous = Vector2::zeros();
+        for point in points {
+            let step = point - previous;
+            assert!(step.x.abs() <= 1 && step.y.abs() <= 1 && step != Vector2::zeros());
+            previous = point;
+
+            // Distance to the ideal line along the minor axis
+            let (major, minor, to_major, to_minor) = if to.x.abs() >= to.y.abs() {
+                (point.x, point.y, to.x, to.y)
+            } else {
+                (point.y, point.x, to.y, to.x)
+            };
+            let ideal = major as f32 * to_minor as f32 / to_major as f32;
+            assert!(
+                (minor as f32 - ideal).abs() <= 0.5,
+                "{point} on line to {to}"
+            );
+        }
+    }
+}
+
+#[test]
+fn move_towards_stops_before_element() {
+    let grid = with_api(
+        "
+        #...
+        ....
+        ....
+        #...
+        ",
+        Vector2::new(0, 0),
+        |api| {
+            let movement = api.move_towards(Vector2::new(0, 10), 10);
+            assert_eq!(
+                movement,
+                Movement {
+                    offset: Vector2::new(0, 2),
+                    obstacle: Some(Obstacle::Element(Vector2::new(0, 1))),
+                }
+            );
+            assert_eq!(api.position, Vector2::new(0, 2));
+        },
+    );
+    assert_eq!(grid, "....\n....\n#...\n#...\n");
+}
+
+#[test]
+fn move_towards_stops_at_border() {
+    let grid = with_api("#...\n....", Vector2::new(0, 0), |api| {
+        let movement = api.move_towards(Vector2::new(6, 2), 10);
+        assert_eq!(movement.offset, Vector2::new(3, 1));
+        assert_eq!(
+            movement.obstacle,
+            Some(Obstacle::OutOfBounds(Vector2::new(1, 0)))
+        );
+    });
+    assert_eq!(grid, "....\n...#\n");
+}
+
+#[test]
+fn move_towards_takes_at_most_max_steps() {
+    let grid = with_api("#...", Vector2::new(0, 0), |api| {
+        let movement = api.move_towards(Vector2::new(3, 0), 2);
+        assert_eq!(movement.offset, Vector2::new(2, 0));
+        assert_eq!(movement.obstacle, None);
+        assert!(movement.moved());
+    });
+    assert_eq!(grid, "..#.\n");
+}
+
+#[test]
+fn raycast_hits_elements_and_border() {
+    with_api(
+        "
+        S...
+        ....
+        #...
+        ",
+        Vector2::new(0, 0),
+        |api| {
+            assert_eq!(
+                api.raycast(Vector2::new(0, 5), 5),
+                Some(Obstacle::Element(Vector2::new(0, 2)))
+            );
+            assert_eq!(
+                api.raycast(Vector2::new(6, 0), 6),
+                Some(Obstacle::OutOfBounds(Vector2::new(4, 0)))
+            );
+            assert_eq!(
+                api.raycast(Vector2::new(-1, 0), 1),
+                Some(Obstacle::OutOfBounds(Vector2::new(-1, 0)))
+            );
+            assert_eq!(api.raycast(Vector2::new(0, 5), 1), None);
+            assert_eq!(api.raycast(Vector2::new(3, 0), 3), None);
+        },
+    );
+}
@@ -0,0 +1,150 @@
+use falling_sand::element_api::ElementApi;
+use falling_sand::elements::sand::new_sand;
+use falling_sand::simulation::{Simulation, TickContext};
+use falling_sand::NEIGHBOURS;
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+
+const SAMPLES: usize = 100_000;
+
+fn with_api(seed: u64, f: impl FnOnce(&mut ElementApi)) {
+    let mut simulation = Simulation::new(3, 3);
+    simulation.matrix[(1, 1)] = Some(new_sand());
+    let mut rng = SplitMix64::seed_from_u64(seed);
+    let context = TickContext {
+        tick: 0,
+        world: &simulation.world,
+        registry: &simulation.registry,
+    };
+    let mut api = ElementApi::new(
+        &mut simulation.matrix,
+        &mut rng,
+        context,
+        Vector2::new(1, 1),
+    );
+    f(&mut api);
+}
+
+/// Pearson's chi-squared statistic of `counts` against the `expected` probabilities
+fn chi_squared(counts: &[usize], expected: &[f64]) -> f64 {
+    let total = counts.iter().sum::<usize>() as f64;
+    counts
+        .iter()
+        .zip(expected)
+        .map(|(&count, &p)| (count as f64 - total * p).powi(2) / (total * p))
+        .sum()
+}
+
+/// Critical values of the chi-squared distribution at a significance level of 0.001
+fn critical_value(degrees_of_freedom: usize) -> f64 {
+    [10.83, 13.82, 16.27, 18.47, 20.52, 22.46, 24.32][degrees_of_freedom - 1]
+}
+
+#[test]
+fn range_is_uniform() {
+    with_api(1, |api| {
+        let mut counts = [0; 6];
+        for _ in 0..SAMPLES {
+            let value = api.range(-2..4);
+            assert!((-2..4).contains(&value));
+            counts[(value + 2) as usize] += 1;
+        }
+        assert!(chi_squared(&counts, &[1.0 / 6.0; 6]) < critical_value(5));
+    });
+}
+
+#[test]
+fn rand_dir_is_uniform() {
+    with_api(2, |api| {
+        let mut counts = [0; 2];
+        for _ in 0..SAMPLES {
+            match api.rand_dir() {
+                -1 => counts[0] += 1,
+                1 => counts[1] += 1,
+                other => panic!("rand_dir returned {other}"),
+            }
+        }
+        assert!(chi_squared(&counts, &[0.5; 2]) < critical_value(1));
+    });
+}
+
+#[test]
+fn chance_matches_probability() {
+    with_api(3, |api| {
+        for probability in [0.1, 0.3, 0.5, 0.9] {
+            let hits = (0..SAMPLES).filter(|_| api.chance(probability)).count();
+            let counts = [hits, SAMPLES - hits];
+            assert!(chi_squared(&counts, &[probability, 1.0 - probability]) < critical_value(1));
+        }
+        assert!((0..1000).all(|_| !api.chance(0.0) && !api.chance(-0.5)));
+        assert!((0..1000).all(|_| api.chance(1.0) && api.chance(1.5)));
+    });
+}
+
+#[test]
+fn pick_is_uniform() {
+    with_api(4, |api| {
+        let items = ['a', 'b', 'c', 'd', 'e'];
+        let mut counts = [0; 5];
+        for _ in 0..SAMPLES {
+            let item = api.pick(&items).unwrap();
+            counts[items.iter().position(|x| x == item).unwrap()] += 1;
+        }
+        assert!(chi_squared(&counts, &[0.2; 5]) < critical_value(4));
+        assert_eq!(api.pick::<char>(&[]), None);
+    });
+}
+
+#[test]
+fn weighted_matches_weights() {
+    with_api(5, |api| {
+        let items = [(0, 1), (1, 0), (2, 3), (3, 6)];
+        let mut counts = [0; 4];
+        for _ in 0..SAMPLES {
+            counts[*api.weighted(&items).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        let counts = [counts[0], counts[2], counts[3]];
+        assert!(chi_squared(&counts, &[0.1, 0.3, 0.6]) < critical_value(2));
+        assert_eq!(api.weighted(&[(0, 0), (1, 0)]), None);
+    });
+}
+
+#[test]
+fn shuffled_neighbours_are_unbiased() {
+    with_api(6, |api| {
+        // counts[i][j] counts how often NEIGHBOURS[j] ended up at index i
+        let mut counts = [[0; 8]; 8];
+        for _ in 0..SAMPLES {
+            let neighbours = api.shuffled_neighbours();
+            let mut indices = neighbours.map(|x| NEIGHBOURS.iter().position(|&n| n == x).unwrap());
+            for (i, &j) in indices.iter().enumerate() {
+                counts[i][j] += 1;
+            }
+            indices.sort();
+            assert_eq!(indices, [0, 1, 2, 3, 4, 5, 6, 7]);
+        }
+        for row in counts {
+            assert!(chi_squared(&row, &[0.125; 8]) < critical_value(7));
+        }
+    });
+}
+
+#[test]
+fn helpers_are_deterministic() {
+    let draw = |seed| {
+        let mut values = Vec::new();
+        with_api(seed, |api| {
+            for _ in 0..100 {
+                values.push(api.range(0..1000));
+                values.push(api.chance(0.5) as isize);
+                values.push(*api.pick(&[1, 2, 3]).unwrap());
+                values.push(api.shuffled_neighbours()[0].x);
+            }
+        });
+        values
+    };
+    assert_eq!(draw(7), draw(7));
+    assert_ne!(draw(7), draw(8));
+}
@@ -0,0 +1,63 @@
+use falling_sand::color::Color;
+use falling_sand::elements::behaviour::solid_behaviour::SolidBehaviour;
+use falling_sand::elements::element::{Element, ElementProperties};
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use falling_sand::elements::sand::new_sand;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+
+#[derive(Clone, ElementProperties)]
+#[element(name = "Glass", color = (200, 230, 240), swap_priority = 230, behaviour = SolidBehaviour)]
+pub struct GlassProperties {
+    color: Color,
+    visited: bool,
+}
+
+/// Registers `constructor` under `id` in a builtin registry, which has to fail,
+/// and returns the error message
+fn register_error(id: ElementId, constructor: fn() -> Element) -> String {
+    let mut registry = ElementRegistry::builtin();
+    let error = registry.register(id, constructor).err().unwrap();
+    assert_eq!(registry.len(), 3);
+    assert!(registry.get_by_name("Glass").is_none());
+    format!("{error:#}")
+}
+
+#[test]
+fn rejects_reserved_id() {
+    let error = register_error(ElementId::EMPTY, new_glass);
+    assert!(error.contains("reserved"), "{error}");
+}
+
+#[test]
+fn rejects_duplicate_id() {
+    let error = register_error(ElementId::SAND, new_glass);
+    assert!(error.contains("already used by Sand"), "{error}");
+}
+
+#[test]
+fn rejects_duplicate_name() {
+    let error = register_error(ElementId(10), new_sand);
+    assert!(error.contains("name is already used"), "{error}");
+}
+
+#[test]
+fn created_elements_round_trip() {
+    let mut registry = ElementRegistry::builtin();
+    assert_eq!(registry.next_free_id(), ElementId(4));
+    registry.register(ElementId(20), new_glass).unwrap();
+    assert_eq!(registry.next_free_id(), ElementId(21));
+
+    let mut rng = SplitMix64::seed_from_u64(0);
+    for element in registry.iter() {
+        let created = registry.create(element.id, &mut rng).unwrap();
+        assert_eq!(created.properties.name(), element.name);
+        assert_eq!(registry.id_of(&created), Some(element.id));
+
+        let created = registry.create_by_name(&element.name, &mut rng).unwrap();
+        assert_eq!(registry.id_of(&created), Some(element.id));
+        assert_eq!(registry.get_by_name(&element.name).unwrap().id, element.id);
+    }
+    assert!(registry.create(ElementId(21), &mut rng).is_none());
+    assert!(registry.create_by_name("Lava", &mut rng).is_none());
+}
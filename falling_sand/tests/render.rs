@@ -0,0 +1,129 @@
+use anyhow::Result;
+use falling_sand::elements::registry::ElementId;
+use falling_sand::render::{element_counts, run, Frame, InputEvent, Renderer, Session, MAX_BRUSH};
+use falling_sand::simulation::Simulation;
+use nalgebra::Vector2;
+use std::time::Duration;
+
+/// Reports one batch of prepared events per poll and remembers what it drew
+struct ScriptedRenderer {
+    batches: Vec<Vec<InputEvent>>,
+    frames: Vec<(u64, bool)>,
+}
+
+impl Renderer for ScriptedRenderer {
+    fn render(&mut self, frame: &Frame) -> Result<()> {
+        self.frames.push((frame.tick, frame.paused));
+        Ok(())
+    }
+
+    fn poll_events(&mut self, timeout: Duration) -> Result<Vec<InputEvent>> {
+        if self.batches.is_empty() {
+            return Ok(vec![InputEvent::Quit]);
+        }
+        std::thread::sleep(timeout);
+        Ok(self.batches.remove(0))
+    }
+}
+
+#[test]
+fn session_paints_selected_element() {
+    let mut session = Session::new(Simulation::new(4, 3));
+    let first = session.palette[0];
+    assert_eq!(session.selected_element(), Some(first));
+
+    session.handle(InputEvent::SelectPrevious);
+    assert_eq!(session.selected_element(), Some(first));
+    session.handle(InputEvent::SelectNext);
+    let second = session.selected_element().unwrap();
+    assert_ne!(second, first);
+
+    session.handle(InputEvent::Paint(Vector2::new(3, 2)));
+    let cell = session.simulation.matrix[(2, 3)].as_ref().unwrap();
+    assert_eq!(session.simulation.registry.id_of(cell), Some(second));
+    // Outside of the grid
+    session.handle(InputEvent::Paint(Vector2::new(4, 0)));
+
+    session.handle(InputEvent::SelectEraser);
+    assert!(session.frame().selected.is_none());
+    session.handle(InputEvent::Paint(Vector2::new(3, 2)));
+    assert!(session.simulation.matrix[(2, 3)].is_none());
+    session.handle(InputEvent::SelectPrevious);
+    assert_eq!(session.selected, Some(session.palette.len() - 1));
+
+    session.handle(InputEvent::Select(second));
+    assert_eq!(session.selected_element(), Some(second));
+    session.handle(InputEvent::Select(ElementId(1000)));
+    assert_eq!(session.selected_element(), Some(second));
+}
+
+#[test]
+fn brush_paints_squares() {
+    let mut session = Session::new(Simulation::new(5, 4));
+    session.handle(InputEvent::ShrinkBrush);
+    assert_eq!(session.brush, 1);
+    for _ in 0..2 {
+        session.handle(InputEvent::GrowBrush);
+    }
+    assert_eq!(session.frame().brush, 3);
+
+    session.handle(InputEvent::Paint(Vector2::new(1, 1)));
+    session.handle(InputEvent::Paint(Vector2::new(4, 3)));
+    let painted = |x: usize, y: usize| session.simulation.matrix[(y, x)].is_some();
+    let count = (0..5)
+        .flat_map(|x| (0..4).map(move |y| (x, y)))
+        .filter(|&(x, y)| painted(x, y))
+        .count();
+    // A full square in the corner and a clipped one at the border
+    assert_eq!(count, 9 + 4);
+    assert!(painted(0, 0) && painted(2, 2) && painted(3, 2) && painted(4, 3));
+    assert!(!painted(3, 0) && !painted(3, 1) && !painted(4, 1));
+
+    for _ in 0..MAX_BRUSH + 5 {
+        session.handle(InputEvent::GrowBrush);
+    }
+    assert_eq!(session.brush, MAX_BRUSH);
+}
+
+#[test]
+fn run_ticks_until_quit() {
+    let mut session = Session::new(Simulation::new(4, 4));
+    let sand = session.palette.iter().position(|x| *x == ElementId::SAND);
+    session.selected = sand;
+    let mut renderer = ScriptedRenderer {
+        batches: vec![
+            vec![InputEvent::Paint(Vector2::new(1, 0))],
+            vec![InputEvent::TogglePause],
+            vec![],
+        ],
+        frames: Vec::new(),
+    };
+    run(&mut renderer, &mut session).unwrap();
+
+    assert!(session.quit);
+    assert!(session.paused);
+    assert_eq!(session.simulation.tick_count(), 1);
+    assert!(session.simulation.matrix[(1, 1)].is_some());
+    assert_eq!(renderer.frames, vec![(0, false), (1, false), (1, true)]);
+}
+
+#[test]
+fn counts_elements() {
+    let simulation = Simulation::from_ascii(
+        "
+        S.W
+        SS.
+        ###
+        ",
+    )
+    .unwrap();
+    let counts = element_counts(&simulation.matrix, &simulation.registry);
+    assert_eq!(counts.len(), 3);
+    for (id, count) in [
+        (ElementId::SAND, 3),
+        (ElementId::WATER, 1),
+        (ElementId::STONE, 3),
+    ] {
+        assert!(counts.contains(&(id, count)));
+    }
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "serde")]
+
+use falling_sand::elements::definition::load_definitions;
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use falling_sand::save::{load_simulation, save_simulation};
+use falling_sand::simulation::Simulation;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::path::Path;
+use std::sync::Arc;
+
+const SALT: &str = r#"[(name: "Salt", color: (240, 240, 235), color_variation: 20, swap_priority: 200, behaviour: [Powder])]"#;
+
+fn registry_with_salt(salt_id: u16) -> Arc<ElementRegistry> {
+    let mut registry = ElementRegistry::builtin();
+    load_definitions(
+        &SALT.replace("(name", &format!("(id: {salt_id}, name")),
+        Path::new(""),
+        &mut registry,
+    )
+    .unwrap();
+    Arc::new(registry)
+}
+
+fn simulation() -> Simulation {
+    let mut simulation = Simulation::new_with_rand(24, 16, SplitMix64::seed_from_u64(7));
+    simulation.registry = registry_with_salt(100);
+    for x in 0..24 {
+        let name = ["Sand", "Water", "Salt"][x % 3];
+        for y in 0..6 {
+            simulation.matrix[(y, x)] = simulation
+                .registry
+                .create_by_name(name, &mut simulation.rng);
+        }
+        simulation.matrix[(15, x)] = simulation
+            .registry
+            .create(ElementId::STONE, &mut simulation.rng);
+    }
+    for _ in 0..5 {
+        simulation.tick();
+    }
+    simulation
+}
+
+/// Name and color of every cell
+fn cells(simulation: &Simulation) -> Vec<Option<(String, [u8; 4])>> {
+    simulation
+        .matrix
+        .iter()
+        .map(|cell| {
+            cell.as_ref().map(|element| {
+                let color = element.properties.color();
+                (
+                    element.properties.name().to_string(),
+                    [color.red, color.green, color.blue, color.alpha],
+                )
+            })
+        })
+        .collect()
+}
+
+fn save(simulation: &Simulation) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    save_simulation(simulation, &mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trip_continues_identically() {
+    let mut original = simulation();
+    let bytes = save(&original);
+    let mut loaded = load_simulation(bytes.as_slice(), Arc::clone(&original.registry)).unwrap();
+
+    assert_eq!(loaded.tick_count(), original.tick_count());
+    assert_eq!(cells(&loaded), cells(&original));
+    for _ in 0..20 {
+        original.tick();
+        loaded.tick();
+    }
+    assert_eq!(cells(&loaded), cells(&original));
+}
+
+#[test]
+fn run_length_encoding_is_compact() {
+    let empty = Simulation::new_with_rand(500, 500, SplitMix64::seed_from_u64(0));
+    assert!(save(&empty).len() < 100);
+}
+
+#[test]
+fn varied_colors_stay_compact() {
+    let mut salt = Simulation::new_with_rand(100, 100, SplitMix64::seed_from_u64(0));
+    salt.registry = registry_with_salt(100);
+    for cell in salt.matrix.iter_mut() {
+        *cell = salt.registry.create_by_name("Salt", &mut salt.rng);
+    }
+    // Every cell is its own run of about 8 bytes because of the varied color
+    assert!(save(&salt).len() < 10 * 100 * 100);
+}
+
+#[test]
+fn palette_survives_changed_ids() {
+    let original = simulation();
+    let bytes = save(&original);
+    let loaded = load_simulation(bytes.as_slice(), registry_with_salt(200)).unwrap();
+    assert_eq!(cells(&loaded), cells(&original));
+}
+
+#[test]
+fn reports_broken_files() {
+    let bytes = save(&simulation());
+    let error = |bytes: &[u8]| {
+        let error = load_simulation(bytes, registry_with_salt(100))
+            .err()
+            .unwrap();
+        format!("{error:#}")
+    };
+
+    assert!(error(&bytes[..10]).contains("truncated"));
+    assert!(error(&bytes[..bytes.len() - 3]).contains("checksum"));
+
+    let mut corrupted = bytes.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0xff;
+    assert!(error(&corrupted).contains("corrupted"));
+
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(error(&wrong_magic).contains("Not a world file"));
+
+    let mut newer = bytes.clone();
+    newer[8..12].copy_from_slice(&99u32.to_le_bytes());
+    assert!(error(&newer).contains("newer"));
+
+    // A valid checksum over a body claiming a world of 2^40 cells
+    let mut huge = bytes[..20].to_vec();
+    for _ in 0..2 {
+        huge.push(252);
+        huge.extend_from_slice(&(1u32 << 20).to_le_bytes());
+    }
+    huge.extend_from_slice(&bytes[22..]);
+    let checksum = huge[8..12]
+        .iter()
+        .chain(&huge[20..])
+        .fold(0xcbf29ce484222325u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    huge[12..20].copy_from_slice(&checksum.to_le_bytes());
+    assert!(error(&huge).contains("too large"));
+
+    let unknown_salt = load_simulation(bytes.as_slice(), Arc::new(ElementRegistry::builtin()));
+    assert!(format!("{:#}", unknown_salt.err().unwrap()).contains("Salt"));
+}
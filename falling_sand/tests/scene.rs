@@ -0,0 +1,71 @@
+#![cfg(feature = "scene")]
+
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use falling_sand::scene::{decode_scene, encode_scene};
+use falling_sand::simulation::{ElementMatrix, Simulation};
+
+fn names(matrix: &ElementMatrix) -> Vec<Option<String>> {
+    matrix
+        .iter()
+        .map(|cell| cell.as_ref().map(|x| x.properties.name().to_string()))
+        .collect()
+}
+
+fn scene() -> Simulation {
+    let mut simulation = Simulation::new(100, 100);
+    for x in 0..100 {
+        for y in 0..100 {
+            let id = match (x + y) % 7 {
+                0 => ElementId::SAND,
+                1 | 2 => ElementId::WATER,
+                3 => ElementId::STONE,
+                _ => continue,
+            };
+            simulation.matrix[(y, x)] = simulation.registry.create(id, &mut simulation.rng);
+        }
+    }
+    simulation
+}
+
+#[test]
+fn round_trip() {
+    let mut simulation = scene();
+    let encoded = encode_scene(&simulation.matrix, &simulation.registry).unwrap();
+    assert!(encoded
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+
+    let decoded = decode_scene(&encoded, &simulation.registry, &mut simulation.rng).unwrap();
+    assert_eq!(decoded.shape(), simulation.matrix.shape());
+    assert_eq!(names(&decoded), names(&simulation.matrix));
+}
+
+#[test]
+fn simple_scenes_are_short() {
+    let mut simulation = Simulation::new(200, 200);
+    for x in 0..200 {
+        simulation.matrix[(199, x)] = simulation
+            .registry
+            .create(ElementId::STONE, &mut simulation.rng);
+    }
+    let encoded = encode_scene(&simulation.matrix, &simulation.registry).unwrap();
+    assert!(encoded.len() < 100, "{encoded}");
+}
+
+#[test]
+fn rejects_invalid_scenes() {
+    let mut simulation = scene();
+    let registry = &simulation.registry;
+    let rng = &mut simulation.rng;
+    let encoded = encode_scene(&simulation.matrix, registry).unwrap();
+
+    assert!(decode_scene("", registry, rng).is_err());
+    assert!(decode_scene("not base64!", registry, rng).is_err());
+    assert!(decode_scene(&encoded[..encoded.len() / 2], registry, rng).is_err());
+    assert!(decode_scene(&format!("B{}", &encoded[1..]), registry, rng).is_err());
+
+    let error = decode_scene(&encoded, &ElementRegistry::new(), rng)
+        .err()
+        .unwrap();
+    assert!(format!("{error:#}").contains("unknown element"));
+}
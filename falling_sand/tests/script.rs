@@ -0,0 +1,125 @@
+#![cfg(feature = "scripting")]
+
+use falling_sand::element_api::ElementApi;
+use falling_sand::elements::behaviour::Behaviour;
+use falling_sand::elements::script::{Script, ScriptBehaviour};
+use falling_sand::simulation::{Simulation, TickContext};
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::fs::{self, File};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Builds a simulation from `ascii` where the element in the top left corner runs `script`
+fn scripted(ascii: &str, script: Arc<Script>) -> Simulation {
+    let mut simulation = Simulation::from_ascii(ascii).unwrap();
+    let element = simulation.matrix[(0, 0)].as_mut().unwrap();
+    element.behaviour = Arc::new(ScriptBehaviour { script });
+    simulation
+}
+
+#[test]
+fn script_swaps_down() {
+    let script = Script::compile("fn update(api) { api.swap(0, 1) }").unwrap();
+    let mut simulation = scripted(
+        "
+        #.
+        ..
+        ",
+        script,
+    );
+    simulation.tick();
+    assert_eq!(simulation.to_ascii(), "..\n#.\n");
+}
+
+#[test]
+fn script_spawns_elements() {
+    let script =
+        Script::compile(r#"fn update(api) { api.spawn_element(1, 0, "Water"); true }"#).unwrap();
+    let mut simulation = scripted("#..", script);
+    simulation.tick();
+    assert_eq!(simulation.to_ascii(), "#W.\n");
+}
+
+#[test]
+fn compile_errors_are_returned() {
+    let error = Script::compile("fn update(api) {").err().unwrap();
+    assert!(format!("{error:#}").contains("Failed to compile"));
+}
+
+#[test]
+fn reload_picks_up_changes() {
+    let path =
+        std::env::temp_dir().join(format!("falling_sand_reload_{}.rhai", std::process::id()));
+    fs::write(&path, "fn update(api) { api.swap(0, 1) }").unwrap();
+    let script = Script::load(&path).unwrap();
+    assert!(!script.reload_if_changed().unwrap());
+
+    fs::write(&path, "fn update(api) { api.swap(1, 0) }").unwrap();
+    // File systems with coarse timestamps could otherwise report the old modification time
+    let modified = SystemTime::now() + Duration::from_secs(10);
+    File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(modified))
+        .unwrap();
+    let reloaded = script.reload_if_changed();
+    let unchanged = script.reload_if_changed();
+    fs::remove_file(&path).unwrap();
+    assert!(reloaded.unwrap());
+    assert!(!unchanged.unwrap());
+
+    let mut simulation = scripted("#.", script);
+    simulation.tick();
+    assert_eq!(simulation.to_ascii(), ".#\n");
+}
+
+#[test]
+fn failing_script_restores_state() {
+    let script = Script::compile(
+        r#"
+        fn update(api) {
+            api.range(0, 10);
+            api.swap(0, 1);
+            throw "broken";
+        }
+        "#,
+    )
+    .unwrap();
+    let behaviour = ScriptBehaviour { script };
+    let mut simulation = Simulation::from_ascii("#.\n..").unwrap();
+    let mut rng = SplitMix64::seed_from_u64(3);
+    let mut expected_rng = rng.clone();
+    // The script draws one number before failing
+    let mut scratch = Simulation::from_ascii("#.\n..").unwrap();
+    let context = TickContext {
+        tick: 0,
+        world: &scratch.world,
+        registry: &scratch.registry,
+    };
+    ElementApi::new(
+        &mut scratch.matrix,
+        &mut expected_rng,
+        context,
+        Vector2::new(0, 0),
+    )
+    .range(0..10);
+
+    let context = TickContext {
+        tick: 0,
+        world: &simulation.world,
+        registry: &simulation.registry,
+    };
+    let mut api = ElementApi::new(
+        &mut simulation.matrix,
+        &mut rng,
+        context,
+        Vector2::new(0, 0),
+    );
+    let error = behaviour.update(&mut api).err().unwrap();
+    assert!(format!("{error:#}").contains("broken"));
+    assert_eq!(api.position, Vector2::new(0, 1));
+    assert_eq!(rng, expected_rng);
+    assert_eq!(simulation.to_ascii(), "..\n#.\n");
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "serde")]
+
+use falling_sand::color::Color;
+use falling_sand::elements::behaviour::powder_behaviour::PowderBehaviour;
+use falling_sand::elements::definition::load_definitions;
+use falling_sand::elements::element::{Element, ElementProperties};
+use falling_sand::elements::registry::{ElementId, ElementRegistry};
+use falling_sand::elements::saved::{ElementSeed, SavedElement};
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use serde::de::DeserializeSeed;
+use std::path::Path;
+
+#[derive(Clone, ElementProperties)]
+#[element(name = "Ember", color = (200, 80, 20), swap_priority = 200, behaviour = PowderBehaviour)]
+struct EmberProperties {
+    color: Color,
+    visited: bool,
+    #[element(state)]
+    lifetime: u32,
+    #[element(state)]
+    temperature: f32,
+}
+
+fn registry() -> ElementRegistry {
+    let mut registry = ElementRegistry::builtin();
+    registry.register(ElementId(50), new_ember).unwrap();
+    load_definitions(
+        r#"[(id: 51, name: "Salt", color: (240, 240, 235), color_variation: 30, swap_priority: 200, behaviour: [Powder])]"#,
+        Path::new(""),
+        &mut registry,
+    )
+    .unwrap();
+    registry
+}
+
+fn round_trip(element: &Element, registry: &ElementRegistry) -> Element {
+    let text = ron::to_string(element).unwrap();
+    let mut deserializer = ron::Deserializer::from_str(&text).unwrap();
+    ElementSeed { registry }
+        .deserialize(&mut deserializer)
+        .unwrap()
+}
+
+#[test]
+fn round_trip_keeps_custom_state() {
+    let registry = registry();
+    let mut ember = EmberProperties {
+        color: Color::new(1, 2, 3),
+        visited: true,
+        lifetime: 17,
+        temperature: 812.5,
+    };
+    let element = Element {
+        properties: Box::new(ember.clone()),
+        behaviour: new_ember().behaviour,
+    };
+
+    let restored = round_trip(&element, &registry);
+    assert_eq!(restored.properties.name(), "Ember");
+    assert_eq!(restored.properties.state(), element.properties.state());
+    assert_eq!(restored.properties.color(), Color::new(1, 2, 3));
+    assert!(!restored.properties.visited());
+
+    ember.lifetime = 0;
+    assert_ne!(ember.state(), element.properties.state());
+}
+
+#[test]
+fn round_trip_keeps_color_variation() {
+    let registry = registry();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    for _ in 0..10 {
+        let salt = registry.create_by_name("Salt", &mut rng).unwrap();
+        let restored = round_trip(&salt, &registry);
+        assert_eq!(restored.properties.color(), salt.properties.color());
+    }
+}
+
+#[test]
+fn resolves_by_name_then_id() {
+    let registry = registry();
+    let sand = registry
+        .create(ElementId::SAND, &mut SplitMix64::seed_from_u64(0))
+        .unwrap();
+
+    let mut saved = SavedElement::new(&sand, &registry);
+    assert_eq!(saved.id, Some(ElementId::SAND));
+    saved.id = Some(ElementId::STONE);
+    assert_eq!(saved.restore(&registry).unwrap().properties.name(), "Sand");
+
+    saved.name = "Renamed Sand".to_string();
+    assert_eq!(saved.restore(&registry).unwrap().properties.name(), "Stone");
+
+    saved.id = None;
+    assert!(saved.restore(&registry).is_err());
+}
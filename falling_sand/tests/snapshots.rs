@@ -0,0 +1,233 @@
+//! Golden snapshot tests of the builtin elements.
+//!
+//! Every scenario runs a seeded simulation from a start grid and compares the result
+//! with `tests/snapshots/<name>.txt`. Run with `BLESS_SNAPSHOTS=1` to write the current results
+//! as new snapshots, then review the changes before committing them.
+
+use falling_sand::simulation::Simulation;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+const BLESS_VARIABLE: &str = "BLESS_SNAPSHOTS";
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{name}.txt"))
+}
+
+/// Lines of `expected` and `actual` side by side, marking lines which differ
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    let mut diff = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let expected = expected.get(i).copied().unwrap_or("");
+        let actual = actual.get(i).copied().unwrap_or("");
+        if expected == actual {
+            writeln!(diff, "  {expected}").unwrap();
+        } else {
+            writeln!(diff, "- {expected}").unwrap();
+            writeln!(diff, "+ {actual}").unwrap();
+        }
+    }
+    diff
+}
+
+/// Runs `start` for `ticks` ticks and compares the start and end grid with the snapshot `name`
+fn check_snapshot(name: &str, start: &str, ticks: u64) {
+    let mut simulation = Simulation::from_ascii(start).unwrap();
+    let mut actual = format!("start:\n{}", simulation.to_ascii());
+    for _ in 0..ticks {
+        simulation.tick();
+    }
+    write!(actual, "\nafter {ticks} ticks:\n{}", simulation.to_ascii()).unwrap();
+
+    let path = snapshot_path(name);
+    if std::env::var(BLESS_VARIABLE).is_ok_and(|x| x == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "Couldnt read snapshot {}: {err}\nRun with {BLESS_VARIABLE}=1 to create it",
+            path.display()
+        )
+    });
+    // Git may check out the snapshots with CRLF line endings
+    let expected = expected.replace("\r\n", "\n");
+    if expected != actual {
+        panic!(
+            "Snapshot {name} doesn't match, - expected + actual:\n{}\nRun with {BLESS_VARIABLE}=1 to accept the changes",
+            diff(&expected, &actual)
+        );
+    }
+}
+
+#[test]
+fn sand_forms_pile() {
+    check_snapshot(
+        "sand_forms_pile",
+        "
+        .....SSS.....
+        .....SSS.....
+        .....SSS.....
+        .....SSS.....
+        .....SSS.....
+        .............
+        .............
+        .............
+        .............
+        .............
+        .............
+        .............
+        #############
+        ",
+        60,
+    );
+}
+
+#[test]
+fn sand_stacks_on_stone() {
+    check_snapshot(
+        "sand_stacks_on_stone",
+        "
+        ...SS....
+        ...SS....
+        .........
+        ..###....
+        .........
+        .........
+        .........
+        .........
+        .........
+        ",
+        30,
+    );
+}
+
+#[test]
+fn sand_sinks_in_water() {
+    check_snapshot(
+        "sand_sinks_in_water",
+        "
+        ..SSS..
+        .......
+        WWWWWWW
+        WWWWWWW
+        WWWWWWW
+        .......
+        #######
+        ",
+        40,
+    );
+}
+
+#[test]
+fn water_levels_out() {
+    check_snapshot(
+        "water_levels_out",
+        "
+        W.........
+        W.........
+        W.........
+        W.........
+        W.........
+        W.........
+        WW........
+        WW........
+        WW........
+        ##########
+        ",
+        120,
+    );
+}
+
+#[test]
+fn water_fills_basin() {
+    check_snapshot(
+        "water_fills_basin",
+        "
+        ...WWW...
+        ...WWW...
+        .........
+        .........
+        .........
+        #.......#
+        #.......#
+        #.......#
+        #########
+        ",
+        80,
+    );
+}
+
+#[test]
+fn stone_falls_and_stacks() {
+    check_snapshot(
+        "stone_falls_and_stacks",
+        "
+        .##..#
+        ......
+        ..#...
+        ......
+        ......
+        ......
+        ",
+        10,
+    );
+}
+
+#[test]
+fn stone_sinks_through_water() {
+    check_snapshot(
+        "stone_sinks_through_water",
+        "
+        .#.#.
+        WWWWW
+        WWWWW
+        WWWWW
+        .....
+        ",
+        20,
+    );
+}
+
+#[test]
+fn water_spreads_in_wide_grid() {
+    check_snapshot(
+        "water_spreads_in_wide_grid",
+        "
+        .........WWW..........
+        .........WWW..........
+        .........WWW..........
+        ######################
+        ",
+        60,
+    );
+}
+
+#[test]
+fn sand_falls_in_tall_grid() {
+    check_snapshot(
+        "sand_falls_in_tall_grid",
+        "
+        .SS.
+        .SS.
+        ....
+        ....
+        ....
+        ....
+        ....
+        ....
+        ....
+        ....
+        ####
+        ",
+        20,
+    );
+}
@@ -0,0 +1,117 @@
+use falling_sand::element_api::ElementApi;
+use falling_sand::elements::definition::load_definitions;
+use falling_sand::elements::registry::ElementRegistry;
+use falling_sand::elements::tags::{Category, Tag, Tags};
+use falling_sand::simulation::{Simulation, TickContext};
+use nalgebra::Vector2;
+use rand_core::SeedableRng;
+use rand_xoshiro::SplitMix64;
+use std::path::Path;
+
+#[test]
+fn tags_are_sets() {
+    let mut tags = Tags::empty();
+    assert_eq!(tags.iter().count(), 0);
+    assert!(Tag::ALL.iter().all(|&tag| !tags.contains(tag)));
+
+    tags.insert(Tag::Soluble);
+    tags.insert(Tag::Liquid);
+    tags.insert(Tag::Liquid);
+    assert!(tags.contains(Tag::Liquid) && tags.contains(Tag::Soluble));
+    assert!(!tags.contains(Tag::Powder));
+    // Iterates in the order of `Tag::ALL`, without duplicates
+    assert_eq!(tags.iter().collect::<Vec<_>>(), [Tag::Liquid, Tag::Soluble]);
+
+    assert_eq!(tags, Tags::new(&[Tag::Soluble, Tag::Liquid]));
+    assert_eq!(
+        tags,
+        [Tag::Liquid, Tag::Soluble, Tag::Liquid]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(Tags::new(&Tag::ALL).iter().collect::<Vec<_>>(), Tag::ALL);
+}
+
+#[test]
+fn parses_tags() {
+    assert_eq!("liquid".parse::<Tag>().unwrap(), Tag::Liquid);
+    assert_eq!("Static".parse::<Tag>().unwrap(), Tag::Static);
+    assert!("Sticky".parse::<Tag>().is_err());
+}
+
+#[test]
+fn categories_follow_tags() {
+    assert_eq!(
+        Tags::new(&[Tag::Flammable, Tag::Powder]).category(),
+        Category::Powder
+    );
+    assert_eq!(Tags::new(&[Tag::Static]).category(), Category::Solid);
+    assert_eq!(
+        Tags::new(&[Tag::Gas, Tag::Static]).category(),
+        Category::Gas
+    );
+    assert_eq!(Tags::new(&[Tag::Conductive]).category(), Category::Other);
+    assert_eq!(Tags::empty().category(), Category::Other);
+}
+
+#[test]
+fn has_tag_outside_and_in_empty_cells() {
+    let mut simulation = Simulation::from_ascii("W.").unwrap();
+    let mut rng = SplitMix64::seed_from_u64(0);
+    let context = TickContext {
+        tick: 0,
+        world: &simulation.world,
+        registry: &simulation.registry,
+    };
+    let api = ElementApi::new(
+        &mut simulation.matrix,
+        &mut rng,
+        context,
+        Vector2::new(0, 0),
+    );
+
+    assert!(api.has_tag(Vector2::zeros(), Tag::Liquid));
+    assert!(!api.has_tag(Vector2::zeros(), Tag::Powder));
+    assert!(!api.has_tag(Vector2::new(1, 0), Tag::Liquid));
+    assert_eq!(api.tags_at(Vector2::new(1, 0)), Tags::empty());
+    for outside in [Vector2::new(-1, 0), Vector2::new(0, 1), Vector2::new(2, 0)] {
+        assert_eq!(api.tags_at(outside), Tags::empty());
+        assert!(!api.has_tag(outside, Tag::Liquid));
+    }
+}
+
+#[test]
+fn groups_by_category_in_registration_order() {
+    let mut registry = ElementRegistry::builtin();
+    load_definitions(
+        r#"[
+            (name: "Thing", color: (1, 2, 3), swap_priority: 1, behaviour: [Solid]),
+            (name: "Marble", color: (230, 230, 230), swap_priority: 255, behaviour: [Solid], tags: [Static]),
+            (name: "Cloud", color: (200, 210, 220), swap_priority: 255, behaviour: [Solid], tags: [Gas]),
+            (name: "Oil", color: (92, 64, 38), swap_priority: 30, behaviour: [Liquid(spread: 3)], tags: [Liquid]),
+            (name: "Ash", color: (90, 90, 90), swap_priority: 200, behaviour: [Powder], tags: [Powder]),
+        ]"#,
+        Path::new(""),
+        &mut registry,
+    )
+    .unwrap();
+
+    let groups = registry
+        .by_category()
+        .into_iter()
+        .map(|(category, elements)| {
+            let names = elements.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+            (category, names)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        groups,
+        [
+            (Category::Powder, vec!["Sand", "Ash"]),
+            (Category::Liquid, vec!["Water", "Oil"]),
+            (Category::Gas, vec!["Cloud"]),
+            (Category::Solid, vec!["Stone", "Marble"]),
+            (Category::Other, vec!["Thing"]),
+        ]
+    );
+}
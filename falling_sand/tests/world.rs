@@ -0,0 +1,60 @@
+use anyhow::Result;
+use falling_sand::element_api::ElementApi;
+use falling_sand::elements::behaviour::Behaviour;
+use falling_sand::simulation::Simulation;
+use falling_sand::{LEFT, RIGHT};
+use nalgebra::Vector2;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+
+/// Remembers the tick of every call
+struct Recording {
+    ticks: Arc<Mutex<Vec<u64>>>,
+}
+
+impl Behaviour for Recording {
+    fn update(&self, api: &mut ElementApi) -> Result<ControlFlow<()>> {
+        self.ticks.lock().unwrap().push(api.context.tick);
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+#[test]
+fn tick_count_reaches_behaviours() {
+    let mut simulation = Simulation::from_ascii("#").unwrap();
+    let ticks = Arc::new(Mutex::new(Vec::new()));
+    simulation.matrix[(0, 0)].as_mut().unwrap().behaviour = Arc::new(Recording {
+        ticks: Arc::clone(&ticks),
+    });
+
+    assert_eq!(simulation.tick_count(), 0);
+    for _ in 0..3 {
+        simulation.tick();
+    }
+    assert_eq!(simulation.tick_count(), 3);
+    assert_eq!(*ticks.lock().unwrap(), [0, 1, 2]);
+}
+
+#[test]
+fn sand_falls_with_sideways_gravity() {
+    let mut simulation = Simulation::from_ascii(
+        "
+        S..#
+        ....
+        ",
+    )
+    .unwrap();
+    simulation.world.gravity = RIGHT;
+    assert_eq!(simulation.world.side(), Vector2::new(0, 1));
+    for _ in 0..10 {
+        simulation.tick();
+    }
+    assert_eq!(simulation.to_ascii(), "...#\n...S\n");
+
+    simulation.world.gravity = LEFT;
+    for _ in 0..10 {
+        simulation.tick();
+    }
+    // Stone falls too
+    assert_eq!(simulation.to_ascii(), "#...\nS...\n");
+}
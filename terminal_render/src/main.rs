@@ -0,0 +1,27 @@
+mod terminal;
+
+use crate::terminal::{TerminalRenderer, STATUS_LINES};
+use anyhow::Result;
+use falling_sand::elements::definition::load_definitions_file;
+use falling_sand::elements::registry::ElementRegistry;
+use falling_sand::render::{run, Session};
+use falling_sand::simulation::Simulation;
+use std::sync::Arc;
+
+/// Runs a simulation filling the terminal, with the elements defined in the file passed as first argument
+fn main() -> Result<()> {
+    let mut registry = ElementRegistry::builtin();
+    if let Some(path) = std::env::args().nth(1) {
+        load_definitions_file(path, &mut registry)?;
+    }
+
+    let (columns, rows) = crossterm::terminal::size()?;
+    // Every character shows two cells above each other
+    let height = (rows as usize).saturating_sub(STATUS_LINES) * 2;
+    let mut simulation = Simulation::new(columns as usize, height);
+    simulation.registry = Arc::new(registry);
+
+    let mut session = Session::new(simulation);
+    let mut renderer = TerminalRenderer::new()?;
+    run(&mut renderer, &mut session)
+}
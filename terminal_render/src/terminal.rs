@@ -0,0 +1,203 @@
+use anyhow::Result;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEventKind,
+};
+use crossterm::style::{Color, Colors, Print, ResetColor, SetColors};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use falling_sand::idx;
+use falling_sand::render::{Frame, InputEvent, Renderer};
+use falling_sand::simulation::ElementMatrix;
+use nalgebra::Vector2;
+use std::io::{stdout, Stdout, Write};
+use std::time::Duration;
+
+/// Lines below the grid used for the status
+pub const STATUS_LINES: usize = 1;
+/// Color of empty cells
+const BACKGROUND: Color = Color::Rgb {
+    r: 255,
+    g: 255,
+    b: 255,
+};
+/// Shows the top cell in the foreground and the bottom cell in the background color
+const HALF_BLOCK: char = '▀';
+
+/// Draws the grid with truecolor half blocks, two cells per character
+pub struct TerminalRenderer {
+    out: Stdout,
+    /// Colors of the characters currently on screen, one row after another
+    drawn: Vec<Option<Colors>>,
+    /// Size of the terminal in characters
+    columns: u16,
+    rows: u16,
+}
+
+impl TerminalRenderer {
+    /// Switches the terminal to an alternate screen with raw input and mouse capture,
+    /// which is undone when the renderer is dropped
+    pub fn new() -> Result<Self> {
+        let mut out = stdout();
+        enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        let (columns, rows) = size()?;
+        Ok(TerminalRenderer {
+            out,
+            drawn: Vec::new(),
+            columns,
+            rows,
+        })
+    }
+
+    /// Forgets what is on screen, so the next frame is drawn completely
+    fn invalidate(&mut self, columns: u16, rows: u16) -> Result<()> {
+        self.columns = columns;
+        self.rows = rows;
+        self.drawn.clear();
+        queue!(self.out, ResetColor, Clear(ClearType::All))?;
+        Ok(())
+    }
+
+    /// Queues the characters whose colors changed since the last frame
+    fn queue_grid(&mut self, matrix: &ElementMatrix) -> Result<()> {
+        let columns = (self.columns as usize).min(matrix.ncols());
+        let rows = (self.rows as usize)
+            .saturating_sub(STATUS_LINES)
+            .min(matrix.nrows().div_ceil(2));
+        self.drawn.resize(columns * rows, None);
+
+        let mut cursor = None;
+        let mut colors = None;
+        for row in 0..rows {
+            for column in 0..columns {
+                let cell = |y: usize| {
+                    let position = Vector2::new(column, y);
+                    if y >= matrix.nrows() {
+                        return Color::Reset;
+                    }
+                    matrix[idx!(position)]
+                        .as_ref()
+                        .map_or(BACKGROUND, |element| {
+                            let c = element.properties.color();
+                            Color::Rgb {
+                                r: c.red,
+                                g: c.green,
+                                b: c.blue,
+                            }
+                        })
+                };
+                let new = Colors::new(cell(row * 2), cell(row * 2 + 1));
+                let drawn = &mut self.drawn[row * columns + column];
+                if *drawn == Some(new) {
+                    continue;
+                }
+                *drawn = Some(new);
+
+                if cursor != Some((column, row)) {
+                    queue!(self.out, MoveTo(column as u16, row as u16))?;
+                }
+                if colors != Some(new) {
+                    queue!(self.out, SetColors(new))?;
+                    colors = Some(new);
+                }
+                queue!(self.out, Print(HALF_BLOCK))?;
+                cursor = Some((column + 1, row));
+            }
+        }
+        Ok(())
+    }
+
+    /// Queues the status line below the grid
+    fn queue_status(&mut self, frame: &Frame) -> Result<()> {
+        let row = (self.rows as usize).saturating_sub(STATUS_LINES);
+        let element = frame.selected.map_or("Eraser", |x| x.name.as_str());
+        let state = if frame.paused { "paused" } else { "running" };
+        let mut status = format!(
+            " {element} | brush {} | tick {} {state} | j/l select, e eraser, [/] brush, space pause, q quit",
+            frame.brush, frame.tick
+        );
+        status.truncate(
+            status
+                .char_indices()
+                .nth(self.columns as usize)
+                .map_or(status.len(), |(i, _)| i),
+        );
+        queue!(
+            self.out,
+            ResetColor,
+            MoveTo(0, row as u16),
+            Clear(ClearType::CurrentLine),
+            Print(status)
+        )?;
+        Ok(())
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn render(&mut self, frame: &Frame) -> Result<()> {
+        let (columns, rows) = size()?;
+        if (columns, rows) != (self.columns, self.rows) {
+            self.invalidate(columns, rows)?;
+        }
+        self.queue_grid(frame.matrix)?;
+        self.queue_status(frame)?;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn poll_events(&mut self, timeout: Duration) -> Result<Vec<InputEvent>> {
+        let mut events = Vec::new();
+        let mut timeout = timeout;
+        while poll(timeout)? {
+            // Only wait for the first event
+            timeout = Duration::ZERO;
+            match read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        events.push(InputEvent::Quit)
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => events.push(InputEvent::Quit),
+                    KeyCode::Char('j') | KeyCode::Left => events.push(InputEvent::SelectPrevious),
+                    KeyCode::Char('l') | KeyCode::Right => events.push(InputEvent::SelectNext),
+                    KeyCode::Char('e') => events.push(InputEvent::SelectEraser),
+                    KeyCode::Char(' ') => events.push(InputEvent::TogglePause),
+                    KeyCode::Char('[') => events.push(InputEvent::ShrinkBrush),
+                    KeyCode::Char(']') => events.push(InputEvent::GrowBrush),
+                    _ => (),
+                },
+                Event::Mouse(mouse) => {
+                    if let MouseEventKind::Down(MouseButton::Left)
+                    | MouseEventKind::Drag(MouseButton::Left) = mouse.kind
+                    {
+                        // A character covers two cells, so both of them are painted
+                        let x = mouse.column as usize;
+                        let y = mouse.row as usize * 2;
+                        events.push(InputEvent::Paint(Vector2::new(x, y)));
+                        events.push(InputEvent::Paint(Vector2::new(x, y + 1)));
+                    }
+                }
+                Event::Resize(..) => events.push(InputEvent::Redraw),
+                _ => (),
+            }
+        }
+        Ok(events)
+    }
+}
+
+impl Drop for TerminalRenderer {
+    fn drop(&mut self) {
+        let _ = execute!(
+            self.out,
+            ResetColor,
+            Show,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        );
+        let _ = disable_raw_mode();
+    }
+}
@@ -0,0 +1,412 @@
+use crate::viewport::Viewport;
+use std::borrow::Cow;
+use wgpu::*;
+
+/// Brightness of the glow added to the scene
+const INTENSITY: f32 = 1.5;
+
+/// How much effort goes into the glow of emissive elements
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BloomQuality {
+    Off,
+    /// Blurs once at a quarter of the window resolution
+    Low,
+    /// Blurs twice at half of the window resolution
+    High,
+}
+
+impl BloomQuality {
+    /// Low for the GL backend, which is used for WebGL2, high otherwise
+    pub fn for_backend(backend: Backend) -> Self {
+        match backend {
+            Backend::Gl => BloomQuality::Low,
+            _ => BloomQuality::High,
+        }
+    }
+
+    /// The quality after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            BloomQuality::Off => BloomQuality::Low,
+            BloomQuality::Low => BloomQuality::High,
+            BloomQuality::High => BloomQuality::Off,
+        }
+    }
+
+    /// Size of a pixel of the bloom targets in window pixels
+    fn downscale(self) -> u32 {
+        match self {
+            BloomQuality::Off | BloomQuality::Low => 4,
+            BloomQuality::High => 2,
+        }
+    }
+
+    /// How often the horizontal and vertical blur run
+    fn iterations(self) -> usize {
+        match self {
+            BloomQuality::Off => 0,
+            BloomQuality::Low => 1,
+            BloomQuality::High => 2,
+        }
+    }
+}
+
+/// The two targets the glow is blurred between and the bind groups reading them
+struct BloomTargets {
+    width: u32,
+    height: u32,
+    /// Holds the emission and the final glow
+    first: TextureView,
+    second: TextureView,
+    /// Blurs `first` horizontally into `second`
+    horizontal: BindGroup,
+    /// Blurs `second` vertically into `first`
+    vertical: BindGroup,
+    /// Reads `first` to add it to the scene
+    composite: BindGroup,
+}
+
+/// Post processing which makes emissive elements glow.
+/// The emission of the cells is drawn into a separate smaller target, blurred and added to the scene
+pub struct Bloom {
+    pub quality: BloomQuality,
+    format: TextureFormat,
+    emission_pipeline: RenderPipeline,
+    blur_pipeline: RenderPipeline,
+    composite_pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    horizontal_uniform: Buffer,
+    vertical_uniform: Buffer,
+    composite_uniform: Buffer,
+    targets: Option<BloomTargets>,
+}
+
+impl Bloom {
+    /// # Arguments
+    ///
+    /// * `grid_shader` - Module of the grid, its `fs_emission` draws the emission
+    /// * `grid_layout` - Layout of the pipeline drawing the grid
+    /// * `surface_format` - Format of the scene the glow is added to
+    pub fn new(
+        device: &Device,
+        adapter: &Adapter,
+        grid_shader: &ShaderModule,
+        grid_layout: &PipelineLayout,
+        surface_format: TextureFormat,
+    ) -> Self {
+        // Float targets keep bright glows from clipping, but WebGL2 often can't render to them
+        let float_features = adapter.get_texture_format_features(TextureFormat::Rgba16Float);
+        let format = if float_features
+            .allowed_usages
+            .contains(TextureUsages::RENDER_ATTACHMENT)
+            && float_features
+                .flags
+                .contains(TextureFormatFeatureFlags::FILTERABLE)
+        {
+            TextureFormat::Rgba16Float
+        } else {
+            TextureFormat::Rgba8Unorm
+        };
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("bloom"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("bloom.wgsl"))),
+        });
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("bloom"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let post_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("bloom"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |label: &str,
+                        module: &ShaderModule,
+                        pipeline_layout: &PipelineLayout,
+                        entry_point: &str,
+                        target: ColorTargetState| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(pipeline_layout),
+                vertex: VertexState {
+                    module,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module,
+                    entry_point,
+                    targets: &[Some(target)],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let emission_pipeline = pipeline(
+            "emission",
+            grid_shader,
+            grid_layout,
+            "fs_emission",
+            format.into(),
+        );
+        let blur_pipeline = pipeline("blur", &shader, &post_layout, "fs_blur", format.into());
+        let composite_pipeline = pipeline(
+            "composite",
+            &shader,
+            &post_layout,
+            "fs_composite",
+            ColorTargetState {
+                format: surface_format,
+                blend: Some(BlendState {
+                    color: BlendComponent {
+                        src_factor: BlendFactor::One,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    },
+                    alpha: BlendComponent {
+                        src_factor: BlendFactor::Zero,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    },
+                }),
+                write_mask: ColorWrites::ALL,
+            },
+        );
+
+        // Linear sampling smooths the glow when it is scaled up to the window
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("bloom"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        });
+        let uniform = |label: &str| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size: std::mem::size_of::<[f32; 4]>() as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+
+        Bloom {
+            quality: BloomQuality::for_backend(adapter.get_info().backend),
+            format,
+            emission_pipeline,
+            blur_pipeline,
+            composite_pipeline,
+            layout,
+            sampler,
+            horizontal_uniform: uniform("horizontal blur"),
+            vertical_uniform: uniform("vertical blur"),
+            composite_uniform: uniform("composite"),
+            targets: None,
+        }
+    }
+
+    /// Creates the targets for a scene of `width` x `height` pixels, unless they already have the right size
+    fn prepare_targets(&mut self, device: &Device, queue: &Queue, width: u32, height: u32) {
+        let downscale = self.quality.downscale();
+        let width = width.div_ceil(downscale).max(1);
+        let height = height.div_ceil(downscale).max(1);
+        if self
+            .targets
+            .as_ref()
+            .is_some_and(|x| x.width == width && x.height == height)
+        {
+            return;
+        }
+
+        let target = |label: &str| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: self.format,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        let first = target("bloom");
+        let second = target("bloom blurred horizontally");
+        let bind_group = |label: &str, source: &TextureView, uniform: &Buffer| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some(label),
+                layout: &self.layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&self.sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: uniform.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let uniform = |step: [f32; 2], intensity: f32| {
+            [step[0], step[1], intensity, 0.0]
+                .map(f32::to_le_bytes)
+                .concat()
+        };
+        queue.write_buffer(
+            &self.horizontal_uniform,
+            0,
+            &uniform([1.0 / width as f32, 0.0], 0.0),
+        );
+        queue.write_buffer(
+            &self.vertical_uniform,
+            0,
+            &uniform([0.0, 1.0 / height as f32], 0.0),
+        );
+        queue.write_buffer(&self.composite_uniform, 0, &uniform([0.0; 2], INTENSITY));
+
+        self.targets = Some(BloomTargets {
+            width,
+            height,
+            horizontal: bind_group("horizontal blur", &first, &self.horizontal_uniform),
+            vertical: bind_group("vertical blur", &second, &self.vertical_uniform),
+            composite: bind_group("composite", &first, &self.composite_uniform),
+            first,
+            second,
+        });
+    }
+
+    /// Draws the emission of the grid bound to `grid_bind_groups`, blurs it and adds it to `scene`.
+    /// Does nothing if the quality is off
+    ///
+    /// # Arguments
+    ///
+    /// * `scene` - Target the grid was drawn to, `width` x `height` pixels large
+    /// * `viewport` - Rectangle of the scene the grid was drawn to
+    /// * `grid_bind_groups` - Bind groups of the grid pipeline
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        scene: &TextureView,
+        width: u32,
+        height: u32,
+        viewport: Viewport,
+        grid_bind_groups: &[&BindGroup],
+    ) {
+        if self.quality == BloomQuality::Off {
+            return;
+        }
+        self.prepare_targets(device, queue, width, height);
+        let Some(targets) = &self.targets else {
+            return;
+        };
+        let downscale = self.quality.downscale() as f32;
+
+        {
+            let mut render_pass = begin_pass(
+                encoder,
+                "emission",
+                &targets.first,
+                LoadOp::Clear(Color::BLACK),
+            );
+            let x = viewport.x / downscale;
+            let y = viewport.y / downscale;
+            let w = (viewport.width / downscale).min(targets.width as f32 - x);
+            let h = (viewport.height / downscale).min(targets.height as f32 - y);
+            if w > 0.0 && h > 0.0 {
+                render_pass.set_viewport(x, y, w, h, 0.0, 1.0);
+                render_pass.set_pipeline(&self.emission_pipeline);
+                for (i, bind_group) in grid_bind_groups.iter().enumerate() {
+                    render_pass.set_bind_group(i as u32, bind_group, &[]);
+                }
+                render_pass.draw(0..6, 0..1);
+            }
+        }
+        for _ in 0..self.quality.iterations() {
+            for (target, bind_group) in [
+                (&targets.second, &targets.horizontal),
+                (&targets.first, &targets.vertical),
+            ] {
+                let mut render_pass =
+                    begin_pass(encoder, "blur", target, LoadOp::Clear(Color::BLACK));
+                render_pass.set_pipeline(&self.blur_pipeline);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(0..6, 0..1);
+            }
+        }
+        let mut render_pass = begin_pass(encoder, "composite", scene, LoadOp::Load);
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.set_bind_group(0, &targets.composite, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+/// Starts a pass drawing into `target`
+fn begin_pass<'a>(
+    encoder: &'a mut CommandEncoder,
+    label: &str,
+    target: &'a TextureView,
+    load: LoadOp<Color>,
+) -> RenderPass<'a> {
+    encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: Operations {
+                load,
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    })
+}
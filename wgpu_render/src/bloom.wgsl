@@ -0,0 +1,56 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct Post {
+    // Distance between two samples of the blur in uv
+    step: vec2<f32>,
+    // Brightness of the glow when it is added to the scene
+    intensity: f32,
+};
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> post: Post;
+
+// Two triangles covering the target, so no vertex buffer is needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let uv = corners[index];
+
+    var out: VertexOutput;
+    out.uv = uv;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+// One direction of a separable 9 tap gaussian blur
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4<f32> {
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    var color = textureSample(source, source_sampler, in.uv).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = post.step * f32(i);
+        color += textureSample(source, source_sampler, in.uv + offset).rgb * weights[i];
+        color += textureSample(source, source_sampler, in.uv - offset).rgb * weights[i];
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// Adds the blurred glow to the scene, the pipeline blends additively
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSample(source, source_sampler, in.uv).rgb * post.intensity, 1.0);
+}
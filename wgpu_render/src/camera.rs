@@ -0,0 +1,139 @@
+use nalgebra::Vector2;
+use winit::keyboard::{Key, NamedKey};
+
+/// Largest zoom, where a cell covers this many times its unzoomed size
+pub const MAX_ZOOM: f32 = 64.0;
+/// Zoom factor of one step of the mouse wheel or a key press
+pub const ZOOM_STEP: f32 = 1.25;
+/// Fraction of the visible area moved by a key press
+pub const PAN_STEP: f32 = 0.1;
+
+/// Zoomed and panned view of the grid.
+/// Positions are relative to the grid, (0, 0) is its top left and (1, 1) its bottom right corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// Position of the grid in the center of the viewport
+    pub center: Vector2<f32>,
+    /// 1 shows the whole grid, larger values show less of it
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            center: Vector2::new(0.5, 0.5),
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    /// Converts a position relative to the viewport into one relative to the grid
+    pub fn grid_position(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.center + (point - Vector2::new(0.5, 0.5)) / self.zoom
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the grid under `point` (relative to the viewport) in place
+    pub fn zoom_at(&mut self, factor: f32, point: Vector2<f32>) {
+        let fixed = self.grid_position(point);
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        self.center = fixed - (point - Vector2::new(0.5, 0.5)) / self.zoom;
+        self.clamp();
+    }
+
+    /// Moves the grid by `delta` relative to the viewport, like dragging it
+    pub fn pan(&mut self, delta: Vector2<f32>) {
+        self.center -= delta / self.zoom;
+        self.clamp();
+    }
+
+    /// Keeps the visible area inside the grid
+    fn clamp(&mut self) {
+        let half = 0.5 / self.zoom;
+        self.center = self.center.map(|x| x.clamp(half, 1.0 - half));
+    }
+
+    /// Applies the keyboard shortcut `key`, returns false if it isn't one.
+    /// `+` and `-` zoom, the arrow keys pan and `0` resets the camera
+    pub fn handle_key(&mut self, key: &Key) -> bool {
+        let center = Vector2::new(0.5, 0.5);
+        match key.as_ref() {
+            Key::Character("+" | "=") => self.zoom_at(ZOOM_STEP, center),
+            Key::Character("-") => self.zoom_at(1.0 / ZOOM_STEP, center),
+            Key::Character("0") => *self = Camera::default(),
+            Key::Named(NamedKey::ArrowLeft) => self.pan(Vector2::new(PAN_STEP, 0.0)),
+            Key::Named(NamedKey::ArrowRight) => self.pan(Vector2::new(-PAN_STEP, 0.0)),
+            Key::Named(NamedKey::ArrowUp) => self.pan(Vector2::new(0.0, PAN_STEP)),
+            Key::Named(NamedKey::ArrowDown) => self.pan(Vector2::new(0.0, -PAN_STEP)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Contents of the camera uniform of the shader: the offset of the visible area,
+    /// the fraction of the grid it covers and padding
+    pub fn uniform(&self) -> [f32; 4] {
+        let scale = 1.0 / self.zoom;
+        let offset = self.center - Vector2::new(0.5, 0.5) * scale;
+        [offset.x, offset.y, scale, 0.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vector2<f32>, b: Vector2<f32>) {
+        assert!((a - b).norm() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn zoom_keeps_point_under_cursor() {
+        let mut camera = Camera::default();
+        for (factor, point) in [
+            (2.0, Vector2::new(0.25, 0.75)),
+            (ZOOM_STEP, Vector2::new(0.9, 0.1)),
+            (1.0 / ZOOM_STEP, Vector2::new(0.6, 0.4)),
+        ] {
+            let before = camera.grid_position(point);
+            camera.zoom_at(factor, point);
+            assert_close(camera.grid_position(point), before);
+        }
+        assert_eq!(camera.zoom, 2.0);
+    }
+
+    #[test]
+    fn zoom_is_limited() {
+        let mut camera = Camera::default();
+        camera.zoom_at(0.5, Vector2::new(0.2, 0.2));
+        assert_eq!(camera, Camera::default());
+        camera.zoom_at(1000.0, Vector2::new(0.5, 0.5));
+        assert_eq!(camera.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn clamp_keeps_view_inside_grid() {
+        let mut camera = Camera::default();
+        camera.zoom_at(4.0, Vector2::new(0.0, 1.0));
+        assert_close(camera.center, Vector2::new(0.125, 0.875));
+
+        camera.pan(Vector2::new(-10.0, 10.0));
+        assert_close(camera.center, Vector2::new(0.875, 0.125));
+        assert_close(
+            camera.grid_position(Vector2::new(1.0, 0.0)),
+            Vector2::new(1.0, 0.0),
+        );
+
+        camera.zoom_at(1.0 / 4.0, Vector2::new(0.5, 0.5));
+        assert_close(camera.center, Vector2::new(0.5, 0.5));
+    }
+
+    #[test]
+    fn uniform_covers_visible_area() {
+        let camera = Camera {
+            center: Vector2::new(0.5, 0.25),
+            zoom: 2.0,
+        };
+        assert_eq!(camera.uniform(), [0.25, 0.0, 0.5, 0.0]);
+    }
+}
@@ -0,0 +1,438 @@
+use crate::font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+use falling_sand::color::Color;
+use falling_sand::elements::registry::RegisteredElement;
+use std::borrow::Cow;
+use web_time::{Duration, Instant};
+use wgpu::*;
+
+/// Size of a pixel of the hud in window pixels
+const SCALE: u32 = 2;
+/// Distance of the hud from the top left corner of the window in window pixels
+const MARGIN: u32 = 8;
+/// Space between the border of the panel and the text
+const PADDING: usize = 3;
+/// Horizontal distance between two characters
+const ADVANCE: usize = GLYPH_WIDTH + 1;
+/// Vertical distance between two lines
+const LINE_HEIGHT: usize = GLYPH_HEIGHT + 2;
+/// Width of a color swatch in front of a line, including the space after it
+const SWATCH_WIDTH: usize = GLYPH_HEIGHT + 3;
+const PANEL_COLOR: [u8; 4] = [0, 0, 0, 170];
+const TEXT_COLOR: [u8; 4] = [255, 255, 255, 255];
+/// Time over which frames and ticks are counted
+const RATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Measures how often something happens per second
+pub struct RateCounter {
+    count: u32,
+    since: Instant,
+    /// Events per second during the last interval
+    pub rate: f32,
+}
+
+impl Default for RateCounter {
+    fn default() -> Self {
+        RateCounter {
+            count: 0,
+            since: Instant::now(),
+            rate: 0.0,
+        }
+    }
+}
+
+impl RateCounter {
+    /// Counts one event
+    pub fn count(&mut self) {
+        self.count_at(Instant::now());
+    }
+
+    /// Counts one event which happened at `now`
+    fn count_at(&mut self, now: Instant) {
+        self.count += 1;
+        let elapsed = now.saturating_duration_since(self.since);
+        if elapsed >= RATE_INTERVAL {
+            self.rate = self.count as f32 / elapsed.as_secs_f32();
+            self.count = 0;
+            self.since = now;
+        }
+    }
+}
+
+/// What the hud shows
+pub struct HudInfo<'a> {
+    /// The element which is painted, `None` for the eraser
+    pub element: Option<&'a RegisteredElement>,
+    /// Width of the painted square in cells
+    pub brush: usize,
+    pub fps: f32,
+    pub tps: f32,
+    pub paused: bool,
+    /// Elements in the grid and how many cells they fill
+    pub counts: Vec<(&'a RegisteredElement, usize)>,
+}
+
+impl HudInfo<'_> {
+    /// Lines of text, each with an optional color swatch in front of it
+    fn lines(&self) -> Vec<(Option<Color>, String)> {
+        let mut lines = vec![
+            match self.element {
+                Some(element) => (Some(element.color), element.name.clone()),
+                None => (None, "Eraser".to_string()),
+            },
+            (None, format!("Brush {}", self.brush)),
+            (None, format!("FPS {:.0}  TPS {:.0}", self.fps, self.tps)),
+            (
+                None,
+                if self.paused { "Paused" } else { "Running" }.to_string(),
+            ),
+        ];
+        lines.extend(
+            self.counts
+                .iter()
+                .map(|(element, count)| (Some(element.color), format!("{} {count}", element.name))),
+        );
+        lines
+    }
+}
+
+/// RGBA pixels the hud is drawn into on the CPU
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 4]) {
+        for y in y..(y + height).min(self.height) {
+            for x in x..(x + width).min(self.width) {
+                let i = (y * self.width + x) * 4;
+                self.pixels[i..i + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, color: [u8; 4]) {
+        for (i, c) in text.chars().enumerate() {
+            for (row, bits) in glyph(c).into_iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill(x + i * ADVANCE + column, y + row, 1, 1, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws `lines` on a translucent panel just large enough for them
+    fn with_lines(lines: &[(Option<Color>, String)]) -> Self {
+        let line_width = |(swatch, text): &(Option<Color>, String)| {
+            swatch.map_or(0, |_| SWATCH_WIDTH) + (text.chars().count() * ADVANCE).saturating_sub(1)
+        };
+        let width = lines.iter().map(line_width).max().unwrap_or(0) + PADDING * 2;
+        let height = (lines.len() * LINE_HEIGHT).saturating_sub(2) + PADDING * 2;
+        let mut canvas = Canvas {
+            width,
+            height,
+            pixels: PANEL_COLOR.repeat(width * height),
+        };
+
+        for (i, (swatch, text)) in lines.iter().enumerate() {
+            let y = PADDING + i * LINE_HEIGHT;
+            let mut x = PADDING;
+            if let Some(c) = swatch {
+                let color = [c.red, c.green, c.blue, 255];
+                canvas.fill(x, y, GLYPH_HEIGHT, GLYPH_HEIGHT, color);
+                x += SWATCH_WIDTH;
+            }
+            canvas.text(x, y, text, TEXT_COLOR);
+        }
+        canvas
+    }
+}
+
+/// The texture the hud was last drawn to
+struct HudTexture {
+    width: u32,
+    height: u32,
+    texture: Texture,
+    bind_group: BindGroup,
+}
+
+/// Text in the top left corner of the window showing the selected element, rates and cell counts.
+/// The text is drawn with a bitmap font on the CPU and blended over the scene as a texture
+pub struct Hud {
+    pub visible: bool,
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    placement: Buffer,
+    texture: Option<HudTexture>,
+}
+
+impl Hud {
+    /// # Arguments
+    ///
+    /// * `surface_format` - Format of the scene the hud is drawn over
+    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("hud"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("hud.wgsl"))),
+        });
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("hud"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("hud"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("hud"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: surface_format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+        // Nearest neighbour sampling keeps the font sharp
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("hud"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..SamplerDescriptor::default()
+        });
+        let placement = device.create_buffer(&BufferDescriptor {
+            label: Some("hud"),
+            size: std::mem::size_of::<[f32; 4]>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Hud {
+            visible: true,
+            pipeline,
+            layout,
+            sampler,
+            placement,
+            texture: None,
+        }
+    }
+
+    /// Draws `info` into the hud texture, which is recreated if the text needs a different size
+    pub fn update(&mut self, device: &Device, queue: &Queue, info: &HudInfo) {
+        let canvas = Canvas::with_lines(&info.lines());
+        let (width, height) = (canvas.width as u32, canvas.height as u32);
+        let max = device.limits().max_texture_dimension_2d;
+        if width > max || height > max {
+            return;
+        }
+
+        let texture = match self.texture.take() {
+            Some(texture) if texture.width == width && texture.height == height => texture,
+            _ => {
+                let texture = device.create_texture(&TextureDescriptor {
+                    label: Some("hud"),
+                    size: Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::Rgba8Unorm,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                    view_formats: &[],
+                });
+                let view = texture.create_view(&TextureViewDescriptor::default());
+                let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("hud"),
+                    layout: &self.layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&view),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::Sampler(&self.sampler),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: self.placement.as_entire_binding(),
+                        },
+                    ],
+                });
+                HudTexture {
+                    width,
+                    height,
+                    texture,
+                    bind_group,
+                }
+            }
+        };
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &canvas.pixels,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.texture = Some(texture);
+    }
+
+    /// Blends the hud over `scene`, which is `width` x `height` pixels large.
+    /// Does nothing if the hud is hidden or wasn't updated yet
+    pub fn render(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        scene: &TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        let Some(texture) = self.texture.as_ref().filter(|_| self.visible) else {
+            return;
+        };
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let placement = [
+            MARGIN as f32 / width,
+            MARGIN as f32 / height,
+            (texture.width * SCALE) as f32 / width,
+            (texture.height * SCALE) as f32 / height,
+        ];
+        queue.write_buffer(
+            &self.placement,
+            0,
+            &placement.map(f32::to_le_bytes).concat(),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("hud"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: scene,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &texture.bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_counter_measures_intervals() {
+        let mut counter = RateCounter::default();
+        let start = counter.since;
+        for _ in 0..9 {
+            counter.count_at(start + Duration::from_millis(100));
+        }
+        assert_eq!(counter.rate, 0.0);
+
+        counter.count_at(start + RATE_INTERVAL);
+        assert_eq!(counter.rate, 20.0);
+        assert_eq!(counter.count, 0);
+        assert_eq!(counter.since, start + RATE_INTERVAL);
+
+        counter.count_at(start + RATE_INTERVAL * 3);
+        assert_eq!(counter.rate, 1.0);
+    }
+
+    #[test]
+    fn canvas_fits_lines() {
+        let swatch = Color::new(10, 20, 30);
+        let canvas =
+            Canvas::with_lines(&[(None, "AB".to_string()), (Some(swatch), "C".to_string())]);
+        let pixel = |x: usize, y: usize| {
+            let i = (y * canvas.width + x) * 4;
+            <[u8; 4]>::try_from(&canvas.pixels[i..i + 4]).unwrap()
+        };
+
+        assert_eq!(canvas.width, SWATCH_WIDTH + GLYPH_WIDTH + PADDING * 2);
+        assert_eq!(canvas.height, LINE_HEIGHT + GLYPH_HEIGHT + PADDING * 2);
+        assert_eq!(canvas.pixels.len(), canvas.width * canvas.height * 4);
+        assert_eq!(pixel(0, 0), PANEL_COLOR);
+        // The top row of an A is lit in the middle
+        assert_eq!(pixel(PADDING, PADDING), PANEL_COLOR);
+        assert_eq!(pixel(PADDING + 2, PADDING), TEXT_COLOR);
+        assert_eq!(pixel(PADDING, PADDING + LINE_HEIGHT), [10, 20, 30, 255]);
+        assert_eq!(
+            pixel(PADDING + SWATCH_WIDTH + 1, PADDING + LINE_HEIGHT),
+            TEXT_COLOR
+        );
+    }
+
+    #[test]
+    fn empty_canvas_is_padding() {
+        let canvas = Canvas::with_lines(&[]);
+        assert_eq!((canvas.width, canvas.height), (PADDING * 2, PADDING * 2));
+    }
+}
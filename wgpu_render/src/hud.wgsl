@@ -0,0 +1,43 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct Placement {
+    // Top left corner of the hud as fraction of the window
+    position: vec2<f32>,
+    // Size of the hud as fraction of the window
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var hud: texture_2d<f32>;
+@group(0) @binding(1)
+var hud_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> placement: Placement;
+
+// Two triangles covering the hud, parts outside of the window are clipped
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let uv = corners[index];
+    let corner = placement.position + uv * placement.size;
+
+    var out: VertexOutput;
+    out.uv = uv;
+    out.clip_position = vec4<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(hud, hud_sampler, in.uv);
+}
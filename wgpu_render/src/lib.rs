@@ -1,58 +1,190 @@
-mod vertex;
+mod bloom;
+mod camera;
+mod font;
+mod hud;
+mod overlay;
+mod texture;
+mod viewport;
 mod wgpu_wrapper;
 
-use crate::vertex::vertices_from_matrix;
+use crate::camera::ZOOM_STEP;
+use crate::hud::{HudInfo, RateCounter};
+use crate::overlay::next_overlay;
 use crate::wgpu_wrapper::WgpuWrapper;
-use falling_sand::elements::element::Element;
-use falling_sand::elements::sand::new_sand;
-use falling_sand::elements::stone::new_stone;
-use falling_sand::elements::water::new_water;
-use falling_sand::idx;
+#[cfg(target_arch = "wasm32")]
+use anyhow::ensure;
+#[cfg(not(target_arch = "wasm32"))]
+use falling_sand::elements::definition::load_definitions_file;
+use falling_sand::elements::registry::ElementRegistry;
+#[cfg(not(target_arch = "wasm32"))]
+use falling_sand::image::raster::{rasterize, RasterOptions};
+use falling_sand::render::{element_counts, Frame, InputEvent, Session, TICK_INTERVAL};
+#[cfg(target_arch = "wasm32")]
+use falling_sand::scene::{decode_scene, encode_scene};
+use falling_sand::simulation::channel::TickActivity;
 use falling_sand::simulation::Simulation;
 use lazy_static::lazy_static;
 use nalgebra::Vector2;
-use std::sync::Mutex;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+#[cfg(target_arch = "wasm32")]
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 use web_time::{Duration, Instant};
-use winit::event::{ElementState, Event, MouseButton, WindowEvent};
+use winit::event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::keyboard::Key;
+use winit::keyboard::{Key, NamedKey};
 use winit::window::Window;
 
-const TICK_SPEED: Duration = Duration::from_millis(10);
+/// Time between two updates of the numbers in the hud
+const HUD_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+/// Pixels of touchpad scrolling which zoom as much as one step of a mouse wheel
+const PIXELS_PER_SCROLL_STEP: f32 = 50.0;
+#[cfg(not(target_arch = "wasm32"))]
+const SCRIPT_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+/// Size of a cell in screenshots in pixels
+#[cfg(not(target_arch = "wasm32"))]
+const SCREENSHOT_SCALE: u32 = 8;
 
 // Game state
 // Has to be a singleton to be accessible through a wasmbind function through js
 lazy_static! {
-    static ref CURRENT_ELEMENT: Mutex<Option<Element>> = Mutex::new(Some(DRAWABLE_ELEMENTS[0]()));
+    static ref REGISTRY: Arc<ElementRegistry> = Arc::new(load_registry());
+    static ref SESSION: Mutex<Session> = Mutex::new(Session::new(new_simulation()));
 }
-const DRAWABLE_ELEMENTS: [fn() -> Element; 3] = [new_sand, new_water, new_stone];
 
-async fn run(event_loop: EventLoop<()>, window: Window) {
+#[cfg(target_arch = "wasm32")]
+lazy_static! {
+    /// Longest side of a grid the device can render, updated once the device is created
+    static ref MAX_GRID_SIDE: AtomicU32 =
+        AtomicU32::new(wgpu::Limits::downlevel_webgl2_defaults().max_texture_dimension_2d);
+}
+
+fn new_simulation() -> Simulation {
     let mut simulation = Simulation::new(100, 100);
+    simulation.registry = Arc::clone(&REGISTRY);
+    simulation
+}
+
+/// Returns the builtin elements and in native builds
+/// the elements defined in the file passed as first argument
+fn load_registry() -> ElementRegistry {
+    #[allow(unused_mut)]
+    let mut registry = ElementRegistry::builtin();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = std::env::args().nth(1) {
+        if let Err(err) = load_definitions_file(path, &mut registry) {
+            log::error!("{err:#}");
+        }
+    }
+    registry
+}
+
+/// Reloads all scripts whose files changed
+#[cfg(not(target_arch = "wasm32"))]
+fn reload_scripts() {
+    for script in REGISTRY.scripts() {
+        match script.reload_if_changed() {
+            Ok(true) => {
+                let path = script.path().unwrap_or(Path::new(""));
+                log::info!("Reloaded {}", path.display());
+            }
+            Ok(false) => (),
+            Err(err) => log::error!("{err:#}"),
+        }
+    }
+}
+
+/// Translates keys which change the session into input events
+fn input_event(key: Key<&str>) -> Option<InputEvent> {
+    match key {
+        Key::Character("j") => Some(InputEvent::SelectPrevious),
+        Key::Character("l") => Some(InputEvent::SelectNext),
+        Key::Character("e") => Some(InputEvent::SelectEraser),
+        Key::Character("[") => Some(InputEvent::ShrinkBrush),
+        Key::Character("]") => Some(InputEvent::GrowBrush),
+        Key::Named(NamedKey::Space) => Some(InputEvent::TogglePause),
+        _ => None,
+    }
+}
+
+/// Collects what the hud shows
+fn hud_info<'a>(frame: &Frame<'a>, fps: &RateCounter, tps: &RateCounter) -> HudInfo<'a> {
+    HudInfo {
+        element: frame.selected,
+        brush: frame.brush,
+        fps: fps.rate,
+        tps: tps.rate,
+        paused: frame.paused,
+        counts: element_counts(frame.matrix, &REGISTRY)
+            .into_iter()
+            .filter_map(|(id, count)| REGISTRY.get(id).map(|element| (element, count)))
+            .collect(),
+    }
+}
+
+/// Saves the current frame to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+fn screenshot(simulation: &Simulation) {
+    let options = RasterOptions {
+        scale: SCREENSHOT_SCALE,
+        ..RasterOptions::default()
+    };
+    let path = format!("screenshot-{}.png", simulation.tick_count());
+    match rasterize(&simulation.matrix, &options).and_then(|image| image.save_png(&path)) {
+        Ok(()) => log::info!("Saved {path}"),
+        Err(err) => log::error!("{err:#}"),
+    }
+}
+
+async fn run(event_loop: EventLoop<()>, window: Window) {
     let mut wgpu = WgpuWrapper::new(&window).await.unwrap();
+    #[cfg(target_arch = "wasm32")]
+    {
+        let max = wgpu.device.limits().max_texture_dimension_2d;
+        MAX_GRID_SIDE.store(max, Ordering::Relaxed);
+        load_scene_from_hash();
+    }
 
-    let drawable_elements = DRAWABLE_ELEMENTS;
-    let mut drawable_index = 0usize;
     let mut last_tick = Instant::now();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut last_reload = Instant::now();
     let mut drawing = false;
-    let mut cursor_position = Vector2::new(0, 0);
+    let mut panning = false;
+    let mut overlay = None;
+    let mut cursor_position = None;
+    let mut fps = RateCounter::default();
+    let mut tps = RateCounter::default();
+    let mut last_hud_update: Option<Instant> = None;
 
     let window = &window;
     event_loop.set_control_flow(ControlFlow::Poll);
     event_loop
         .run(move |event, target| {
-            if last_tick.elapsed() > TICK_SPEED {
-                simulation.tick();
+            let mut session = SESSION.lock().unwrap();
+            if last_tick.elapsed() > TICK_INTERVAL {
+                if !session.paused {
+                    session.simulation.tick();
+                    tps.count();
+                    window.request_redraw();
+                }
                 last_tick = Instant::now();
-                window.request_redraw();
             }
-            if drawing
-                && cursor_position.x < simulation.matrix.ncols()
-                && cursor_position.y < simulation.matrix.nrows()
-            {
-                simulation.matrix[idx!(cursor_position)] = CURRENT_ELEMENT.lock().unwrap().clone();
+            #[cfg(not(target_arch = "wasm32"))]
+            if last_reload.elapsed() > SCRIPT_RELOAD_INTERVAL {
+                reload_scripts();
+                last_reload = Instant::now();
+            }
+            let matrix = &session.simulation.matrix;
+            let grid_size = Vector2::new(matrix.ncols(), matrix.nrows());
+            let cursor_cell = cursor_position.and_then(|position| {
+                wgpu.viewport(matrix)
+                    .cell_at(position, &wgpu.camera, grid_size)
+            });
+            if let Some(cell) = cursor_cell.filter(|_| drawing) {
+                session.handle(InputEvent::Paint(cell));
                 window.request_redraw();
             }
 
@@ -62,48 +194,112 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
             } = event
             {
                 match event {
-                    WindowEvent::Resized(new_size) => wgpu.resize(new_size),
-                    WindowEvent::RedrawRequested => {
-                        let vertices = vertices_from_matrix(&simulation.matrix);
-                        wgpu.render(vertices.as_slice()).unwrap()
+                    WindowEvent::Resized(new_size) => {
+                        wgpu.resize(new_size);
+                        window.request_redraw();
                     }
-                    WindowEvent::MouseInput { button, state, .. } => {
-                        if button == MouseButton::Left {
-                            drawing = state.is_pressed();
+                    WindowEvent::RedrawRequested => {
+                        fps.count();
+                        if wgpu.hud.visible
+                            && last_hud_update.is_none_or(|x| x.elapsed() > HUD_REFRESH_INTERVAL)
+                        {
+                            let info = hud_info(&session.frame(), &fps, &tps);
+                            wgpu.hud.update(&wgpu.device, &wgpu.queue, &info);
+                            last_hud_update = Some(Instant::now());
                         }
+                        let simulation = &session.simulation;
+                        let overlay = overlay.map(|x| simulation.channel(x));
+                        if let Err(err) = wgpu.render(&simulation.matrix, overlay.as_ref()) {
+                            log::error!("{err:#}");
+                        }
+                    }
+                    WindowEvent::MouseInput {
+                        button: MouseButton::Left,
+                        state,
+                        ..
+                    } => {
+                        drawing = state.is_pressed();
+                    }
+                    WindowEvent::MouseInput {
+                        button: MouseButton::Right | MouseButton::Middle,
+                        state,
+                        ..
+                    } => {
+                        panning = state.is_pressed();
                     }
                     WindowEvent::CursorMoved { position, .. } => {
-                        cursor_position = Vector2::new(
-                            ((position.x / wgpu.config.width as f64)
-                                * simulation.matrix.ncols() as f64)
-                                as usize,
-                            ((position.y / wgpu.config.height as f64)
-                                * simulation.matrix.nrows() as f64)
-                                as usize,
-                        );
+                        if let Some(last) = cursor_position.filter(|_| panning) {
+                            let viewport = wgpu.viewport(&session.simulation.matrix);
+                            wgpu.camera
+                                .pan(viewport.relative(position) - viewport.relative(last));
+                            window.request_redraw();
+                        }
+                        cursor_position = Some(position);
+                    }
+                    WindowEvent::CursorLeft { .. } => cursor_position = None,
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        let steps = match delta {
+                            MouseScrollDelta::LineDelta(_, y) => y,
+                            MouseScrollDelta::PixelDelta(position) => {
+                                position.y as f32 / PIXELS_PER_SCROLL_STEP
+                            }
+                        };
+                        let viewport = wgpu.viewport(&session.simulation.matrix);
+                        let point = cursor_position.map_or(Vector2::new(0.5, 0.5), |position| {
+                            viewport.relative(position).map(|x| x.clamp(0.0, 1.0))
+                        });
+                        wgpu.camera.zoom_at(ZOOM_STEP.powf(steps), point);
+                        window.request_redraw();
                     }
-                    WindowEvent::KeyboardInput { event, .. } => {
-                        if event.state == ElementState::Pressed && !event.repeat {
+                    WindowEvent::KeyboardInput { event, .. }
+                        if event.state == ElementState::Pressed =>
+                    {
+                        // Refresh the hud right away, it shows the result of most keys
+                        last_hud_update = None;
+                        if wgpu.camera.handle_key(&event.logical_key) {
+                            window.request_redraw();
+                        } else if let Some(input) =
+                            input_event(event.logical_key.as_ref()).filter(|_| !event.repeat)
+                        {
+                            session.handle(input);
+                            window.request_redraw();
+                        } else if !event.repeat {
                             match event.logical_key.as_ref() {
-                                Key::Character("j") => {
-                                    drawable_index = drawable_index.saturating_sub(1);
-                                    *(CURRENT_ELEMENT.lock().unwrap()) =
-                                        Some(drawable_elements[drawable_index]());
+                                Key::Character("h") => {
+                                    wgpu.hud.visible = !wgpu.hud.visible;
+                                    window.request_redraw();
                                 }
-                                Key::Character("l") => {
-                                    drawable_index =
-                                        (drawable_index + 1).min(drawable_elements.len() - 1);
-                                    *(CURRENT_ELEMENT.lock().unwrap()) =
-                                        Some(drawable_elements[drawable_index]());
+                                Key::Character("o") => {
+                                    overlay = next_overlay(overlay);
+                                    session.simulation.activity = overlay
+                                        .filter(|x| x.needs_activity())
+                                        .map(|_| TickActivity::default());
+                                    log::info!("Overlay {overlay:?}");
+                                    window.request_redraw();
                                 }
+                                Key::Character("f") => {
+                                    wgpu.fit = wgpu.fit.next();
+                                    log::info!("Fit mode {:?}", wgpu.fit);
+                                    window.request_redraw();
+                                }
+                                Key::Character("g") => {
+                                    wgpu.bloom.quality = wgpu.bloom.quality.next();
+                                    log::info!("Bloom quality {:?}", wgpu.bloom.quality);
+                                    window.request_redraw();
+                                }
+                                #[cfg(not(target_arch = "wasm32"))]
+                                Key::Character("p") => screenshot(&session.simulation),
                                 _ => (),
                             }
                         }
                     }
-                    WindowEvent::CloseRequested => target.exit(),
+                    WindowEvent::CloseRequested => session.handle(InputEvent::Quit),
                     _ => (),
                 }
             }
+            if session.quit {
+                target.exit();
+            }
         })
         .unwrap();
 }
@@ -111,25 +307,90 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn elements() -> Vec<String> {
-    DRAWABLE_ELEMENTS
-        .map(|x| x().properties.name().into())
-        .to_vec()
+    SESSION
+        .lock()
+        .unwrap()
+        .palette
+        .iter()
+        .filter_map(|id| REGISTRY.get(*id))
+        .map(|x| x.name.clone())
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn element_category(element: &str) -> Option<String> {
+    REGISTRY
+        .get_by_name(element)
+        .map(|x| x.category().to_string())
 }
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn set_current_element(element: &str) {
-    *(CURRENT_ELEMENT.lock().unwrap()) = DRAWABLE_ELEMENTS
-        .iter()
-        .map(|x| x())
-        .filter(|x| x.properties.name() == element)
-        .next();
+    if let Some(element) = REGISTRY.get_by_name(element) {
+        SESSION
+            .lock()
+            .unwrap()
+            .handle(InputEvent::Select(element.id));
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn set_current_element_delete() {
-    *(CURRENT_ELEMENT.lock().unwrap()) = None;
+    SESSION.lock().unwrap().handle(InputEvent::SelectEraser);
+}
+
+/// Returns the current scene as URL-safe string
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn export_scene() -> Result<String, JsError> {
+    encode_scene(&SESSION.lock().unwrap().simulation.matrix, &REGISTRY)
+        .map_err(|err| JsError::new(&format!("{err:#}")))
+}
+
+/// Replaces the matrix of `simulation` with `scene`.
+/// Fails without changing it if the device can't render the grid of the scene
+#[cfg(target_arch = "wasm32")]
+fn replace_scene(simulation: &mut Simulation, scene: &str) -> anyhow::Result<()> {
+    let matrix = decode_scene(scene, &REGISTRY, &mut simulation.rng)?;
+    let (width, height) = (matrix.ncols(), matrix.nrows());
+    let max = MAX_GRID_SIDE.load(Ordering::Relaxed) as usize;
+    ensure!(
+        width > 0 && height > 0,
+        "The scene of {width}x{height} cells is empty"
+    );
+    ensure!(
+        width.max(height) <= max,
+        "The scene of {width}x{height} cells is larger than the maximum size {max}"
+    );
+    simulation.matrix = matrix;
+    Ok(())
+}
+
+/// Replaces the current scene with one returned by `export_scene`
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn load_scene(scene: &str) -> Result<(), JsError> {
+    let simulation = &mut SESSION.lock().unwrap().simulation;
+    replace_scene(simulation, scene).map_err(|err| JsError::new(&format!("{err:#}")))
+}
+
+/// Loads the scene in the fragment of the page url, if there is one
+#[cfg(target_arch = "wasm32")]
+fn load_scene_from_hash() {
+    let Some(hash) = web_sys::window().and_then(|win| win.location().hash().ok()) else {
+        return;
+    };
+    let scene = hash.trim_start_matches('#');
+    if scene.is_empty() {
+        return;
+    }
+    let simulation = &mut SESSION.lock().unwrap().simulation;
+    if let Err(err) = replace_scene(simulation, scene) {
+        log::error!("Couldnt load scene from url: {err:#}");
+    }
 }
 
 #[allow(dead_code)]
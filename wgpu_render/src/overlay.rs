@@ -0,0 +1,78 @@
+use falling_sand::color::Color;
+use falling_sand::idx;
+use falling_sand::simulation::channel::{Channel, ChannelData};
+use nalgebra::Vector2;
+
+/// Opacity of heatmaps over the cells
+const HEATMAP_ALPHA: u8 = 180;
+/// Smallest range of values a heatmap spreads its colors over
+const MIN_HEATMAP_RANGE: f32 = 1.0;
+
+/// The overlay after `overlay`, going through all channels and then back to no overlay
+pub fn next_overlay(overlay: Option<Channel>) -> Option<Channel> {
+    match overlay {
+        None => Channel::ALL.first().copied(),
+        Some(channel) => Channel::ALL
+            .iter()
+            .skip_while(|x| **x != channel)
+            .nth(1)
+            .copied(),
+    }
+}
+
+/// Blends `data` over RGBA `pixels` written by [`crate::texture::pixels_from_matrix`].
+/// Scalars are drawn as heatmap from blue for the lowest to red for the highest value
+pub fn apply_overlay(pixels: &mut [u8], data: &ChannelData) {
+    match data {
+        ChannelData::Scalar(values) => {
+            let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let range = (max - min).max(MIN_HEATMAP_RANGE);
+            let low = (min + max - range) / 2.0;
+            blend_cells(pixels, values.ncols(), values.len(), |pos| {
+                heatmap((values[idx!(pos)] - low) / range)
+            });
+        }
+        ChannelData::Color(colors) => {
+            blend_cells(pixels, colors.ncols(), colors.len(), |pos| {
+                colors[idx!(pos)]
+            });
+        }
+    }
+}
+
+/// Blends the color of every cell of a grid `width` cells wide over its pixel.
+/// Does nothing if the grid doesn't have `cells` cells
+fn blend_cells(
+    pixels: &mut [u8],
+    width: usize,
+    cells: usize,
+    color: impl Fn(Vector2<usize>) -> Color,
+) {
+    if pixels.len() != cells * 4 {
+        return;
+    }
+    for (i, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        blend(pixel, color(Vector2::new(i % width, i / width)));
+    }
+}
+
+/// Blue for 0, over green to red for 1
+fn heatmap(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::new_with_alpha(
+        channel(t * 2.0 - 1.0),
+        channel(1.0 - (t * 2.0 - 1.0).abs()),
+        channel(1.0 - t * 2.0),
+        HEATMAP_ALPHA,
+    )
+}
+
+/// Draws `color` over `pixel` according to its alpha
+fn blend(pixel: &mut [u8], color: Color) {
+    let alpha = color.alpha as u32;
+    for (old, new) in pixel.iter_mut().zip([color.red, color.green, color.blue]) {
+        *old = ((new as u32 * alpha + *old as u32 * (255 - alpha)) / 255) as u8;
+    }
+}
@@ -1,24 +1,53 @@
-struct VertexInput {
-    @location(0) position: vec2<f32>,
-    @location(1) color: vec4<f32>,
-};
-
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec4<f32>,
+    @location(0) uv: vec2<f32>,
 };
 
+struct Camera {
+    // Top left corner of the visible area of the grid
+    offset: vec2<f32>,
+    // Fraction of the grid which is visible
+    scale: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: Camera;
+
+// Two triangles covering the screen, so no vertex buffer is needed
 @vertex
-fn vs_main(
-    model: VertexInput,
-) -> VertexOutput {
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let uv = corners[index];
+
     var out: VertexOutput;
-    out.color = model.color;
-    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.uv = camera.offset + uv * camera.scale;
+    // uv starts at the top left, clip space at the bottom left
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
     return out;
 }
 
+@group(0) @binding(0)
+var grid: texture_2d<f32>;
+@group(0) @binding(1)
+var grid_sampler: sampler;
+// Colors of the cells scaled by how strongly they glow
+@group(0) @binding(2)
+var emission: texture_2d<f32>;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color);
+    return textureSample(grid, grid_sampler, in.uv);
+}
+
+// Writes the glowing cells into the bloom target
+@fragment
+fn fs_emission(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(emission, grid_sampler, in.uv);
 }
@@ -0,0 +1,223 @@
+use crate::overlay::apply_overlay;
+use anyhow::{ensure, Result};
+use falling_sand::elements::element::Element;
+use falling_sand::idx;
+use falling_sand::simulation::channel::ChannelData;
+use falling_sand::simulation::ElementMatrix;
+use nalgebra::Vector2;
+use wgpu::*;
+
+/// Color of empty cells
+pub const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+
+/// Writes one RGBA pixel per cell into `pixels`, one row of the grid after another
+fn pixels_with(
+    matrix: &ElementMatrix,
+    pixels: &mut Vec<u8>,
+    pixel: impl Fn(Option<&Element>) -> [u8; 4],
+) {
+    pixels.clear();
+    pixels.reserve(matrix.len() * 4);
+    for y in 0..matrix.nrows() {
+        for x in 0..matrix.ncols() {
+            pixels.extend_from_slice(&pixel(matrix[idx!(Vector2::new(x, y))].as_ref()));
+        }
+    }
+}
+
+/// Writes the colors of all cells into `pixels` as RGBA, one row of the grid after another
+pub fn pixels_from_matrix(matrix: &ElementMatrix, pixels: &mut Vec<u8>) {
+    pixels_with(matrix, pixels, |cell| match cell {
+        None => BACKGROUND,
+        Some(element) => {
+            let c = element.properties.color();
+            [c.red, c.green, c.blue, c.alpha]
+        }
+    });
+}
+
+/// Writes the colors of all cells scaled by their emission into `pixels`, black for cells which don't glow
+pub fn emission_from_matrix(matrix: &ElementMatrix, pixels: &mut Vec<u8>) {
+    pixels_with(matrix, pixels, |cell| {
+        let Some(element) = cell else {
+            return [0, 0, 0, 255];
+        };
+        let emission = element.properties.emission().clamp(0.0, 1.0);
+        let c = element.properties.color();
+        let glow = |x: u8| (x as f32 * emission).round() as u8;
+        [glow(c.red), glow(c.green), glow(c.blue), 255]
+    });
+}
+
+/// A texture with one pixel per cell and the pixels last uploaded to it
+struct CellTexture {
+    texture: Texture,
+    view: TextureView,
+    /// Pixels currently in the texture
+    uploaded: Vec<u8>,
+}
+
+impl CellTexture {
+    fn new(device: &Device, label: &str, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            // Not sRGB, so colors end up on screen like the element colors are defined
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        CellTexture {
+            texture,
+            view,
+            uploaded: Vec::new(),
+        }
+    }
+
+    /// Uploads the rows of `pixels` which changed since the last upload with a single `write_texture`.
+    /// Swaps `pixels` with the previously uploaded pixels to reuse their allocation
+    fn upload(&mut self, queue: &Queue, pixels: &mut Vec<u8>, width: u32, height: u32) {
+        let row_bytes = width as usize * 4;
+        if row_bytes == 0 {
+            return;
+        }
+
+        let (first, last) = if self.uploaded.len() == pixels.len() {
+            let changed = |(_, (old, new)): &(usize, (&[u8], &[u8]))| old != new;
+            let rows = || {
+                self.uploaded
+                    .chunks_exact(row_bytes)
+                    .zip(pixels.chunks_exact(row_bytes))
+                    .enumerate()
+            };
+            let Some((first, _)) = rows().find(changed) else {
+                return;
+            };
+            let (last, _) = rows().rev().find(changed).unwrap_or((first, (&[], &[])));
+            (first, last)
+        } else {
+            (0, height as usize - 1)
+        };
+
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: first as u32,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            &pixels[first * row_bytes..(last + 1) * row_bytes],
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(row_bytes as u32),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width,
+                height: (last - first + 1) as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+        std::mem::swap(&mut self.uploaded, pixels);
+    }
+}
+
+/// The colors and emission of all cells, which are kept between frames
+pub struct GridTexture {
+    colors: CellTexture,
+    emission: CellTexture,
+    pub bind_group: BindGroup,
+    pub width: u32,
+    pub height: u32,
+    /// Whether any cell glows
+    pub emissive: bool,
+    /// Reused to avoid an allocation every frame
+    scratch: Vec<u8>,
+}
+
+impl GridTexture {
+    pub fn new(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        let max = device.limits().max_texture_dimension_2d;
+        ensure!(
+            width.max(height) <= max,
+            "The grid of {width}x{height} cells is larger than the maximum texture size {max}"
+        );
+
+        let colors = CellTexture::new(device, "grid", width, height);
+        let emission = CellTexture::new(device, "emission", width, height);
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("grid"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&colors.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&emission.view),
+                },
+            ],
+        });
+
+        Ok(GridTexture {
+            colors,
+            emission,
+            bind_group,
+            width,
+            height,
+            emissive: false,
+            scratch: Vec::new(),
+        })
+    }
+
+    /// Uploads the cells which changed since the last update, with `overlay` blended over them.
+    /// The emission is only uploaded if `with_emission` is set
+    pub fn update(
+        &mut self,
+        queue: &Queue,
+        matrix: &ElementMatrix,
+        overlay: Option<&ChannelData>,
+        with_emission: bool,
+    ) {
+        pixels_from_matrix(matrix, &mut self.scratch);
+        if let Some(overlay) = overlay {
+            apply_overlay(&mut self.scratch, overlay);
+        }
+        self.colors
+            .upload(queue, &mut self.scratch, self.width, self.height);
+
+        self.emissive = false;
+        if with_emission {
+            emission_from_matrix(matrix, &mut self.scratch);
+            self.emissive = self
+                .scratch
+                .chunks_exact(4)
+                .any(|pixel| pixel[..3] != [0, 0, 0]);
+            self.emission
+                .upload(queue, &mut self.scratch, self.width, self.height);
+        }
+    }
+}
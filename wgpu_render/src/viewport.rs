@@ -0,0 +1,156 @@
+use crate::camera::Camera;
+use nalgebra::Vector2;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// How the grid is fitted into a window with a different aspect ratio
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitMode {
+    /// Fills the whole window, cells aren't square
+    Stretch,
+    /// As large as possible with square cells, centered between bars
+    #[default]
+    Letterbox,
+    /// Like letterbox, but cells are a whole number of pixels large.
+    /// Falls back to letterbox if the grid has more cells than the window pixels
+    IntegerScale,
+}
+
+impl FitMode {
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            FitMode::Stretch => FitMode::Letterbox,
+            FitMode::Letterbox => FitMode::IntegerScale,
+            FitMode::IntegerScale => FitMode::Stretch,
+        }
+    }
+}
+
+/// Rectangle of the window the grid is drawn to in pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    /// Places a grid of `grid` cells (width, height) into a window of `window` pixels
+    pub fn fit(mode: FitMode, window: PhysicalSize<u32>, grid: Vector2<usize>) -> Self {
+        let window_width = window.width as f32;
+        let window_height = window.height as f32;
+        if mode == FitMode::Stretch || grid.x == 0 || grid.y == 0 {
+            return Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: window_width,
+                height: window_height,
+            };
+        }
+
+        let mut scale = (window_width / grid.x as f32).min(window_height / grid.y as f32);
+        if mode == FitMode::IntegerScale && scale >= 1.0 {
+            scale = scale.floor();
+        }
+        let width = grid.x as f32 * scale;
+        let height = grid.y as f32 * scale;
+        let mut x = (window_width - width) / 2.0;
+        let mut y = (window_height - height) / 2.0;
+        if mode == FitMode::IntegerScale {
+            // Aligns the cells with the pixels of the window
+            x = x.floor();
+            y = y.floor();
+        }
+        Viewport {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Converts a window position into one relative to the viewport,
+    /// (0, 0) is its top left and (1, 1) its bottom right corner
+    pub fn relative(&self, position: PhysicalPosition<f64>) -> Vector2<f32> {
+        Vector2::new(
+            (position.x as f32 - self.x) / self.width,
+            (position.y as f32 - self.y) / self.height,
+        )
+    }
+
+    /// Returns the cell of a grid of `grid` cells (width, height) seen through `camera`
+    /// under the window position `position`, or `None` if it is outside of the viewport
+    pub fn cell_at(
+        &self,
+        position: PhysicalPosition<f64>,
+        camera: &Camera,
+        grid: Vector2<usize>,
+    ) -> Option<Vector2<usize>> {
+        let relative = self.relative(position);
+        let inside = |x: f32| (0.0..1.0).contains(&x);
+        if grid.x == 0 || grid.y == 0 || !inside(relative.x) || !inside(relative.y) {
+            return None;
+        }
+        let point = camera.grid_position(relative);
+        Some(Vector2::new(
+            ((point.x * grid.x as f32) as usize).min(grid.x - 1),
+            ((point.y * grid.y as f32) as usize).min(grid.y - 1),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: Vector2<usize> = Vector2::new(10, 10);
+
+    fn cell_at(viewport: &Viewport, camera: &Camera, x: f64, y: f64) -> Option<Vector2<usize>> {
+        viewport.cell_at(PhysicalPosition::new(x, y), camera, GRID)
+    }
+
+    #[test]
+    fn letterbox_centers_grid() {
+        let viewport = Viewport::fit(FitMode::Letterbox, PhysicalSize::new(200, 100), GRID);
+        assert_eq!(
+            viewport,
+            Viewport {
+                x: 50.0,
+                y: 0.0,
+                width: 100.0,
+                height: 100.0,
+            }
+        );
+        let camera = Camera::default();
+        assert_eq!(
+            cell_at(&viewport, &camera, 65.0, 99.0),
+            Some(Vector2::new(1, 9))
+        );
+        assert_eq!(cell_at(&viewport, &camera, 10.0, 10.0), None);
+        assert_eq!(cell_at(&viewport, &camera, 150.0, 10.0), None);
+    }
+
+    #[test]
+    fn cell_at_follows_zoom() {
+        let viewport = Viewport::fit(FitMode::Stretch, PhysicalSize::new(100, 100), GRID);
+        let camera = Camera {
+            center: Vector2::new(0.25, 0.75),
+            zoom: 2.0,
+        };
+        assert_eq!(
+            cell_at(&viewport, &camera, 0.0, 0.0),
+            Some(Vector2::new(0, 5))
+        );
+        assert_eq!(
+            cell_at(&viewport, &camera, 50.0, 50.0),
+            Some(Vector2::new(2, 7))
+        );
+        assert_eq!(
+            cell_at(&viewport, &camera, 99.0, 99.0),
+            Some(Vector2::new(4, 9))
+        );
+        assert_eq!(cell_at(&viewport, &camera, -1.0, 50.0), None);
+        assert_eq!(cell_at(&viewport, &camera, 50.0, 100.0), None);
+    }
+}
@@ -1,16 +1,38 @@
-use crate::vertex::Vertex;
+use crate::bloom::{Bloom, BloomQuality};
+use crate::camera::Camera;
+use crate::hud::Hud;
+use crate::texture::GridTexture;
+use crate::viewport::{FitMode, Viewport};
 use anyhow::{Context, Result};
-use bytemuck::cast_slice;
+use falling_sand::simulation::channel::ChannelData;
+use falling_sand::simulation::ElementMatrix;
+use nalgebra::Vector2;
 use std::borrow::Cow;
-use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::*;
 use winit::dpi::PhysicalSize;
 
+/// Color of the bars around the grid when it doesn't fill the window
+const BORDER_COLOR: Color = Color {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 1.0,
+};
+
 pub struct WgpuWrapper<'a> {
     pub surface: Surface<'a>,
     pub device: Device,
     pub queue: Queue,
     pub render_pipeline: RenderPipeline,
+    pub bind_group_layout: BindGroupLayout,
+    pub sampler: Sampler,
+    pub grid: Option<GridTexture>,
+    pub camera_buffer: Buffer,
+    pub camera_bind_group: BindGroup,
+    pub camera: Camera,
+    pub fit: FitMode,
+    pub bloom: Bloom,
+    pub hud: Hud,
     pub config: SurfaceConfiguration,
     pub size: PhysicalSize<u32>,
 }
@@ -51,9 +73,78 @@ impl<'a> WgpuWrapper<'a> {
             source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
         });
 
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("grid"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        // Nearest neighbour sampling keeps the cells sharp
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("grid"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..SamplerDescriptor::default()
+        });
+
+        let camera_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("camera"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let camera_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("camera"),
+            size: std::mem::size_of::<[f32; 4]>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let camera_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("camera"),
+            layout: &camera_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&bind_group_layout, &camera_layout],
             push_constant_ranges: &[],
         });
 
@@ -66,7 +157,7 @@ impl<'a> WgpuWrapper<'a> {
             vertex: VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
+                buffers: &[],
             },
             fragment: Some(FragmentState {
                 module: &shader,
@@ -78,6 +169,14 @@ impl<'a> WgpuWrapper<'a> {
             multisample: MultisampleState::default(),
             multiview: None,
         });
+        let bloom = Bloom::new(
+            &device,
+            &adapter,
+            &shader,
+            &pipeline_layout,
+            swapchain_format,
+        );
+        let hud = Hud::new(&device, swapchain_format);
 
         let config = surface
             .get_default_config(&adapter, size.width, size.height)
@@ -89,6 +188,15 @@ impl<'a> WgpuWrapper<'a> {
             device,
             queue,
             render_pipeline,
+            bind_group_layout,
+            sampler,
+            grid: None,
+            camera_buffer,
+            camera_bind_group,
+            camera: Camera::default(),
+            fit: FitMode::default(),
+            bloom,
+            hud,
             config,
             size,
         })
@@ -103,12 +211,39 @@ impl<'a> WgpuWrapper<'a> {
         }
     }
 
-    pub fn render(&mut self, vertices: &[Vertex]) -> Result<()> {
-        let vertex_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            contents: cast_slice(vertices),
-            usage: BufferUsages::VERTEX,
-        });
+    /// Rectangle of the surface `matrix` is drawn to with the current fit mode
+    pub fn viewport(&self, matrix: &ElementMatrix) -> Viewport {
+        Viewport::fit(
+            self.fit,
+            PhysicalSize::new(self.config.width, self.config.height),
+            Vector2::new(matrix.ncols(), matrix.nrows()),
+        )
+    }
+
+    /// Uploads the changed cells of `matrix` and the camera and draws the grid into the viewport,
+    /// with `overlay` blended over the cells and the hud on top
+    pub fn render(&mut self, matrix: &ElementMatrix, overlay: Option<&ChannelData>) -> Result<()> {
+        let viewport = self.viewport(matrix);
+        let (width, height) = (matrix.ncols() as u32, matrix.nrows() as u32);
+        let grid = match self.grid.take() {
+            Some(grid) if grid.width == width && grid.height == height => grid,
+            _ => GridTexture::new(
+                &self.device,
+                &self.bind_group_layout,
+                &self.sampler,
+                width,
+                height,
+            )?,
+        };
+        let grid = self.grid.insert(grid);
+        grid.update(
+            &self.queue,
+            matrix,
+            overlay,
+            self.bloom.quality != BloomQuality::Off,
+        );
+        let camera = self.camera.uniform().map(f32::to_le_bytes).concat();
+        self.queue.write_buffer(&self.camera_buffer, 0, &camera);
 
         let frame = self
             .surface
@@ -126,7 +261,7 @@ impl<'a> WgpuWrapper<'a> {
                     view: &view,
                     resolve_target: None,
                     ops: Operations {
-                        load: LoadOp::Clear(Color::WHITE),
+                        load: LoadOp::Clear(BORDER_COLOR),
                         store: StoreOp::Store,
                     },
                 })],
@@ -134,10 +269,41 @@ impl<'a> WgpuWrapper<'a> {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            render_pass.draw(0..vertices.len() as _, 0..1);
+            // wgpu rejects empty viewports, which happen for grids without cells
+            if viewport.width >= 1.0 && viewport.height >= 1.0 {
+                render_pass.set_viewport(
+                    viewport.x,
+                    viewport.y,
+                    viewport.width,
+                    viewport.height,
+                    0.0,
+                    1.0,
+                );
+                render_pass.set_pipeline(&self.render_pipeline);
+                render_pass.set_bind_group(0, &grid.bind_group, &[]);
+                render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+                render_pass.draw(0..6, 0..1);
+            }
+        }
+        if grid.emissive {
+            self.bloom.render(
+                &self.device,
+                &self.queue,
+                &mut encoder,
+                &view,
+                self.config.width,
+                self.config.height,
+                viewport,
+                &[&grid.bind_group, &self.camera_bind_group],
+            );
         }
+        self.hud.render(
+            &self.queue,
+            &mut encoder,
+            &view,
+            self.config.width,
+            self.config.height,
+        );
 
         self.queue.submit(Some(encoder.finish()));
         frame.present();